@@ -3,6 +3,7 @@ use crate::{
     vector::Point3,
 };
 
+#[derive(Clone)]
 pub struct Aabb {
     pub x: Interval,
     pub y: Interval,
@@ -42,6 +43,20 @@ impl Aabb {
         Self { x, y, z }
     }
 
+    // widen any side thinner than a small delta, so flat primitives like quads
+    // still have a box with some volume for the ray-slab test
+    #[inline(always)]
+    pub const fn pad_to_minimums(self) -> Self {
+        let delta = 0.0001;
+        let Aabb { x, y, z } = self;
+
+        Self {
+            x: if x.size() < delta { x.expand(delta) } else { x },
+            y: if y.size() < delta { y.expand(delta) } else { y },
+            z: if z.size() < delta { z.expand(delta) } else { z },
+        }
+    }
+
     #[inline(always)]
     pub const fn axis_interval(&self, n: u8) -> &Interval {
         match n {
@@ -60,6 +75,21 @@ impl Aabb {
         }
     }
 
+    #[inline(always)]
+    pub const fn centre(&self) -> Point3 {
+        Point3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
+    // length of the diagonal between the two extreme corners
+    #[inline(always)]
+    pub fn diagonal(&self) -> f32 {
+        Point3::new(self.x.size(), self.y.size(), self.z.size()).len()
+    }
+
     #[inline(always)]
     pub const fn longest_axis(&self) -> u8 {
         if self.x.size() > self.y.size() {
@@ -67,9 +97,18 @@ impl Aabb {
         } else if self.y.size() > self.z.size() { 1 } else { 2 }
     }
 
+    // the slabs of all three axes narrow the same interval, so a ray that
+    // passes each slab at a different time misses
     #[inline(always)]
     pub fn hit(&self, r: &Ray, int: &Interval) -> bool {
-        let Ray { dir, origin } = r;
+        self.clip(r, int).is_some()
+    }
+
+    // the part of `int` where the ray is inside the box, if any
+    #[inline(always)]
+    pub fn clip(&self, r: &Ray, int: &Interval) -> Option<Interval> {
+        let Ray { dir, origin, .. } = r;
+        let mut int = int.clone();
 
         for axis in 0..3 {
             let ax = self.axis_interval(axis);
@@ -87,7 +126,6 @@ impl Aabb {
             let t0 = (ax.min - ray_orig_axis) * adinv;
             let t1 = (ax.max - ray_orig_axis) * adinv;
 
-            let mut int = int.clone();
             if t0 < t1 {
                 if t0 > int.min {
                     int.min = t0;
@@ -105,10 +143,10 @@ impl Aabb {
             }
 
             if int.max <= int.min {
-                return false;
+                return None;
             }
         }
 
-        true
+        Some(int)
     }
 }
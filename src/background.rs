@@ -0,0 +1,91 @@
+use crate::{color::Color, ray::Ray, vector::Vec3};
+
+// radiance seen by rays that escape the scene
+pub trait Background {
+    fn value(&self, r: &Ray) -> Color;
+
+    // backgrounds that can pick directions in proportion to their brightness
+    // are sampled as lights by the camera
+    #[inline(always)]
+    fn can_sample(&self) -> bool {
+        false
+    }
+
+    // a direction towards the background with the radiance arriving from it
+    // and its solid angle density
+    #[inline(always)]
+    fn sample(&self) -> (Vec3, Color, f32) {
+        (Vec3::new(0., 1., 0.), Color::zero(), 0.)
+    }
+
+    // solid angle density of `sample` returning `dir`
+    #[inline(always)]
+    fn pdf(&self, _dir: &Vec3) -> f32 {
+        0.
+    }
+}
+
+// any closure of a ray can be used as a background
+impl<F: Fn(&Ray) -> Color> Background for F {
+    #[inline(always)]
+    fn value(&self, r: &Ray) -> Color {
+        self(r)
+    }
+}
+
+pub struct SolidBackground {
+    pub color: Color,
+}
+
+impl SolidBackground {
+    #[inline(always)]
+    pub const fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Background for SolidBackground {
+    #[inline(always)]
+    fn value(&self, _r: &Ray) -> Color {
+        self.color
+    }
+}
+
+// linear blend between two colors, going from `from` for rays pointing along -axis
+// to `to` for rays pointing along +axis
+pub struct GradientBackground {
+    pub axis: Vec3,
+    pub from: Color,
+    pub to: Color,
+}
+
+impl GradientBackground {
+    #[inline(always)]
+    pub fn new(axis: Vec3, from: Color, to: Color) -> Self {
+        Self {
+            axis: axis.unit_vec(),
+            from,
+            to,
+        }
+    }
+
+    // white at the horizon fading to blue overhead
+    #[inline(always)]
+    pub fn sky() -> Self {
+        Self::new(
+            Vec3::new(0., 1., 0.),
+            Color::one(),
+            Color::new(0.5, 0.7, 1.0),
+        )
+    }
+}
+
+impl Background for GradientBackground {
+    #[inline(always)]
+    fn value(&self, r: &Ray) -> Color {
+        let unit_dir = r.dir.unit_vec();
+        let a = 0.5 * (unit_dir.dot(&self.axis) + 1.0);
+
+        (1.0 - a) * self.from + a * self.to
+    }
+}
@@ -3,7 +3,7 @@ use std::{cmp::Ordering, sync::Arc};
 use crate::hittable::HitRecord;
 use crate::{
     aabb::Aabb,
-    hittable::{Hittable, HittableList},
+    hittable::{Hittable, HittableList, collect_lights},
     ray::{Interval, Ray},
 };
 
@@ -22,7 +22,7 @@ impl BVHNode {
     pub fn from_object_slice(objects: &mut [Arc<dyn Hittable + Sync + Send>]) -> Self {
         let mut bbox = Aabb::empty();
         for obj in objects.iter() {
-            bbox = Aabb::enclose(&bbox, obj.bounding_box())
+            bbox = Aabb::enclose(&bbox, &obj.bounding_box())
         }
 
         let axis = bbox.longest_axis();
@@ -37,7 +37,12 @@ impl BVHNode {
         let left;
         let right;
 
-        if object_span == 1 {
+        if object_span == 0 {
+            // an empty node that nothing hits, rather than splitting forever
+            let empty: Arc<dyn Hittable + Sync + Send> = Arc::new(HittableList::new());
+            left = empty.clone();
+            right = empty;
+        } else if object_span == 1 {
             left = objects[0].clone();
             right = objects[0].clone();
         } else if object_span == 2 {
@@ -51,7 +56,7 @@ impl BVHNode {
             right = Arc::new(Self::from_object_slice(&mut objects[mid..]))
         }
 
-        let bbox = Aabb::enclose(left.bounding_box(), right.bounding_box());
+        let bbox = Aabb::enclose(&left.bounding_box(), &right.bounding_box());
 
         Self { left, right, bbox }
     }
@@ -62,9 +67,9 @@ impl BVHNode {
         b: &Arc<dyn Hittable + Sync + Send>,
         axis_idx: u8,
     ) -> Ordering {
-        let a_axis_int = a.bounding_box().axis_interval(axis_idx);
-        let b_axis_int = b.bounding_box().axis_interval(axis_idx);
-        a_axis_int.min.total_cmp(&b_axis_int.min)
+        let a_min = a.bounding_box().axis_interval(axis_idx).min;
+        let b_min = b.bounding_box().axis_interval(axis_idx).min;
+        a_min.total_cmp(&b_min)
     }
 
     #[inline]
@@ -112,7 +117,16 @@ impl Hittable for BVHNode {
     }
 
     #[inline(always)]
-    fn bounding_box(&self) -> &Aabb {
-        &self.bbox
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        collect_lights(&self.left, lights);
+
+        // leaves holding a single object point both children at it
+        if !Arc::ptr_eq(&self.left, &self.right) {
+            collect_lights(&self.right, lights);
+        }
     }
 }
@@ -1,9 +1,19 @@
-use std::{f32, fs::File, io::BufWriter, time::Instant};
+use std::{
+    f32, fmt,
+    fs::File,
+    io::BufWriter,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use crate::hittable::Hittable;
+use crate::hittable::{HitRecord, Hittable, HittableList};
 use crate::{
+    background::{Background, GradientBackground},
     color::Color,
+    light::{AreaLight, EnvironmentLight, Light},
+    light_sampler::{LightSampler, LightSampling},
     material::Scatter,
+    pdf::MisHeuristic,
     ray::{Interval, Ray},
     vector::{Point3, Vec3},
 };
@@ -35,6 +45,12 @@ pub struct Camera {
     w: Vec3,
     defocus_disk_u: Vec3, // Defocus disk horizontal radius
     defocus_disk_v: Vec3, // Defocus disk vertical radius
+    pub background: Arc<dyn Background + Sync + Send>, // Radiance for rays that miss everything
+    pub shutter: Interval, // Times the shutter is open between, rays are spread uniformly over it
+    pub heuristic: MisHeuristic, // How light and BSDF samples of the same light are weighted
+    pub roulette_depth: usize, // Bounces before paths may be ended early by Russian roulette
+    pub lights: Vec<Arc<dyn Light + Sync + Send>>, // Lights without geometry, like point lights
+    pub light_sampling: LightSampling, // How the light for each light sample is picked
 }
 
 impl Camera {
@@ -109,35 +125,76 @@ impl Camera {
             focus_dist,
             defocus_disk_u,
             defocus_disk_v,
+            background: Arc::new(GradientBackground::sky()),
+            shutter: Interval::new(0., 1.),
+            heuristic: MisHeuristic::Power,
+            roulette_depth: 3,
+            lights: Vec::new(),
+            light_sampling: LightSampling::Tree,
         }
     }
 
     #[inline(always)]
-    fn render_pixel(&self, x: u32, y: u32, world: &(impl Hittable + Sync)) -> Rgb<u8> {
+    fn render_pixel(
+        &self,
+        x: u32,
+        y: u32,
+        world: &(impl Hittable + Sync),
+        lights: &dyn LightSampler,
+        stats: &Mutex<PathStats>,
+    ) -> Rgb<u8> {
         let mut color = Color::zero();
+        let mut pixel_stats = PathStats::default();
         for _ in 0..self.samples_per_pixel {
             let r = self.get_ray(x, y);
-            color += Self::ray_color(&r, world, self.max_bounce_depth);
+            color += self.ray_color(r, world, lights, &mut pixel_stats);
         }
 
+        stats.lock().unwrap().merge(&pixel_stats);
+
         image::Rgb((color * self.pixel_samples_scale).as_rgb().as_array())
     }
 
     pub fn render(&self, world: &(impl Hittable + Sync)) -> Result<()> {
         let now = Instant::now();
+        let lights = self
+            .light_sampling
+            .build(self.collect_lights(world), &world.bounding_box());
+        let stats = Mutex::new(PathStats::default());
         let bar = indicatif::ProgressBar::new(self.image_height as u64 * self.image_width as u64);
         let img = image::ImageBuffer::from_par_fn(self.image_width, self.image_height, |x, y| {
             bar.inc(1);
-            self.render_pixel(x, y, world)
+            self.render_pixel(x, y, world, lights.as_ref(), &stats)
         });
 
         let mut buf = BufWriter::new(File::create("image.png")?);
         img.write_to(&mut buf, image::ImageFormat::Png)?;
         let elapsed_time = now.elapsed();
         println!("Rendering took {} seconds", elapsed_time.as_secs_f32());
+        println!("{}", stats.into_inner().unwrap());
         Ok(())
     }
 
+    // the emissive surfaces in the scene, plus the background if it can be sampled
+    // and the lights added to the camera
+    fn collect_lights(&self, world: &impl Hittable) -> Vec<Arc<dyn Light + Sync + Send>> {
+        let mut shapes = HittableList::new();
+        world.collect_lights(&mut shapes);
+
+        let mut lights: Vec<Arc<dyn Light + Sync + Send>> = shapes
+            .objects
+            .into_iter()
+            .map(|shape| Arc::new(AreaLight::new(shape)) as _)
+            .collect();
+
+        if self.background.can_sample() {
+            lights.push(Arc::new(EnvironmentLight::new(self.background.clone())));
+        }
+
+        lights.extend(self.lights.iter().cloned());
+        lights
+    }
+
     // return a pair within [-0.5, 0.5], [-0.5, 0.5] range
     #[inline(always)]
     fn sample_square() -> (f32, f32) {
@@ -157,7 +214,7 @@ impl Camera {
     #[inline(always)]
     fn get_ray(&self, i: u32, j: u32) -> Ray {
         // Construct a camera ray originating from the defocus disk and directed at a randomly
-        // sampled point around the pixel location i, j.
+        // sampled point around the pixel location i, j, at a random time the shutter is open.
 
         let offset = Self::sample_square();
         let pixel_sample = self.pixel00_loc
@@ -171,32 +228,249 @@ impl Camera {
         };
 
         let ray_dir = pixel_sample - ray_origin;
+        let ray_time = self.shutter.min + fastrand::f32() * self.shutter.size();
+
+        Ray::new(ray_origin, ray_dir, ray_time)
+    }
+
+    // follows the path from `r` bounce by bounce, carrying the product of the
+    // BSDF weights so far in `throughput`
+    #[inline(always)]
+    fn ray_color(
+        &self,
+        mut r: Ray,
+        world: &impl Hittable,
+        lights: &dyn LightSampler,
+        stats: &mut PathStats,
+    ) -> Color {
+        let mut radiance = Color::zero();
+        let mut throughput = Color::one();
+
+        // density the last bounce sampled `r` with, None for camera rays and specular
+        // bounces, which lights cannot be sampled for
+        let mut bsdf_pdf: Option<f32> = None;
+        let mut bounces = 0;
+
+        // absorption of the closed surface the path is inside of, if any, which dims every
+        // segment travelled until the path leaves it again (nesting is not tracked)
+        let mut interior: Option<Color> = None;
+
+        let end = loop {
+            if bounces == self.max_bounce_depth {
+                break PathEnd::Capped;
+            }
+
+            let Some(rec) = world.hit(&r, &Interval::new(0.001, f32::INFINITY)) else {
+                // the ray escaped to the background, through all of the interior if it was
+                // inside a surface that did not close
+                if let Some(sigma) = &interior {
+                    throughput = throughput * beer_lambert(sigma, f32::INFINITY);
+                }
+
+                let mut background = self.background.value(&r);
+                if let Some(bsdf_pdf) = bsdf_pdf
+                    && self.background.can_sample()
+                {
+                    let light_pdf = lights.pdf(&r, f32::INFINITY);
+                    background *= self.heuristic.weight(bsdf_pdf, light_pdf);
+                }
 
-        Ray::new(ray_origin, ray_dir)
+                radiance += throughput * background;
+                break PathEnd::Escaped;
+            };
+
+            if let Some(sigma) = &interior {
+                throughput = throughput * beer_lambert(sigma, rec.t * r.dir.len());
+            }
+
+            let mut emitted = rec.mat.emitted(&r, &rec);
+
+            // the light sample at the previous bounce could also have found this light, but
+            // not the ones behind it
+            if let Some(bsdf_pdf) = bsdf_pdf
+                && rec.mat.is_emissive()
+            {
+                emitted *= self.heuristic.weight(bsdf_pdf, lights.pdf(&r, rec.t));
+            }
+
+            radiance += throughput * emitted;
+
+            match rec.mat.scatter(&r, &rec) {
+                Scatter::Sampled {
+                    r: scattered,
+                    f,
+                    pdf,
+                } => {
+                    if pdf <= 0. {
+                        break PathEnd::Absorbed;
+                    }
+
+                    let direct = self.direct_light(&r, &rec, world, lights, interior.as_ref());
+                    radiance += throughput * direct;
+                    throughput = throughput * f / pdf;
+                    bsdf_pdf = Some(pdf);
+                    interior = Self::interior_after(&rec, &scattered, interior);
+                    r = scattered;
+                }
+                Scatter::Specular(scattered, atten) => {
+                    throughput = throughput * atten;
+                    bsdf_pdf = None;
+                    interior = Self::interior_after(&rec, &scattered, interior);
+                    r = scattered;
+                }
+                Scatter::Absorbed => break PathEnd::Absorbed,
+            }
+
+            bounces += 1;
+
+            // end dim paths early, boosting the survivors so the estimate stays unbiased
+            if bounces >= self.roulette_depth {
+                let survive = throughput.luminance().min(1.);
+                if fastrand::f32() >= survive {
+                    break PathEnd::Roulette;
+                }
+                throughput /= survive;
+            }
+        };
+
+        stats.record(bounces, end);
+        radiance
     }
 
+    // light reaching the hit straight from one light picked by the light sampler, through a
+    // shadow ray towards a point sampled on it, weighted against the BSDF sampling the same
+    // direction
     #[inline(always)]
-    fn ray_color(r: &Ray, world: &impl Hittable, bounce_depth: usize) -> Color {
-        if bounce_depth == 0 {
+    fn direct_light(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        world: &impl Hittable,
+        lights: &dyn LightSampler,
+        interior: Option<&Color>,
+    ) -> Color {
+        let Some((light, pmf)) = lights.sample(&rec.p) else {
+            return Color::zero();
+        };
+        let Some(sample) = light.sample(&rec.p, r_in.time) else {
+            return Color::zero();
+        };
+
+        let f = rec.mat.eval(r_in, rec, &sample.dir);
+        if f.near_zero() {
             return Color::zero();
         }
 
-        if let Some(rec) = world.hit(r, &Interval::new(0.001, f32::INFINITY)) {
-            if let Scatter::Scattered(r, atten) = rec.mat.scatter(r, &rec) {
-                return atten * Self::ray_color(&r, world, bounce_depth - 1);
-            } else {
-                return Color::zero();
-            }
+        let shadow = Ray::new(rec.p, sample.dir, r_in.time);
+        if world
+            .hit(&shadow, &Interval::new(0.001, sample.dist - 0.001))
+            .is_some()
+        {
+            return Color::zero();
         }
 
-        // otherwise a gradient background
-        let unit_dir = r.dir.unit_vec();
-        let a = 0.5 * (unit_dir.y + 1.0);
+        let light_pdf = sample.pdf * pmf;
+        let weight = if light.can_be_hit() {
+            let bsdf_pdf = rec.mat.pdf(r_in, rec, &sample.dir);
+            self.heuristic.weight(light_pdf, bsdf_pdf)
+        } else {
+            1.
+        };
+
+        let mut radiance = sample.radiance;
+        if let Some(sigma) = interior {
+            radiance = radiance * beer_lambert(sigma, sample.dist);
+        }
+
+        (weight / light_pdf) * f * radiance
+    }
+
+    // absorption inside of the next segment, after `scattered` leaves the hit: crossing an
+    // absorbing surface from the front enters it and crossing from the back leaves it
+    #[inline(always)]
+    fn interior_after(rec: &HitRecord, scattered: &Ray, interior: Option<Color>) -> Option<Color> {
+        let Some(sigma) = rec.mat.absorption() else {
+            return interior;
+        };
 
-        (1.0 - a) * Color::one() + a * Color::new(0.5, 0.7, 1.0)
+        // the normal faces the incoming ray, so transmitted rays go against it
+        if scattered.dir.dot(rec.normal()) >= 0. {
+            interior
+        } else if rec.face_normal.is_front() {
+            Some(sigma)
+        } else {
+            None
+        }
     }
 }
 
+// why a path stopped bouncing
+#[derive(Clone, Copy)]
+enum PathEnd {
+    Escaped,
+    Absorbed,
+    Roulette,
+    Capped, // reached max_bounce_depth
+}
+
+// path lengths over a render
+#[derive(Default)]
+struct PathStats {
+    paths: u64,
+    bounces: u64,
+    longest: usize,
+    roulette: u64,
+    capped: u64,
+}
+
+impl PathStats {
+    #[inline(always)]
+    fn record(&mut self, bounces: usize, end: PathEnd) {
+        self.paths += 1;
+        self.bounces += bounces as u64;
+        self.longest = self.longest.max(bounces);
+
+        match end {
+            PathEnd::Roulette => self.roulette += 1,
+            PathEnd::Capped => self.capped += 1,
+            PathEnd::Escaped | PathEnd::Absorbed => {}
+        }
+    }
+
+    fn merge(&mut self, other: &PathStats) {
+        self.paths += other.paths;
+        self.bounces += other.bounces;
+        self.longest = self.longest.max(other.longest);
+        self.roulette += other.roulette;
+        self.capped += other.capped;
+    }
+}
+
+impl fmt::Display for PathStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mean = self.bounces as f64 / self.paths.max(1) as f64;
+        write!(
+            f,
+            "{} paths, {:.2} bounces on average, longest {}, {} ended by Russian roulette, {} hit the depth cap",
+            self.paths, mean, self.longest, self.roulette, self.capped
+        )
+    }
+}
+
+// fraction of light left after `dist` through a medium absorbing `sigma` per unit distance
+#[inline(always)]
+fn beer_lambert(sigma: &Color, dist: f32) -> Color {
+    // channels that absorb nothing pass everything, even over an infinite distance
+    let transmit = |sigma: f32| {
+        if sigma > 0. {
+            (-sigma * dist).exp()
+        } else {
+            1.
+        }
+    };
+    Color::new(transmit(sigma.x), transmit(sigma.y), transmit(sigma.z))
+}
+
 #[inline(always)]
 const fn degrees_to_radians(degrees: f32) -> f32 {
     degrees * f32::consts::PI / 180.0
@@ -29,6 +29,12 @@ impl Color {
         }
     }
 
+    // relative luminance of a linear (Rec. 709) color
+    #[inline(always)]
+    pub const fn luminance(&self) -> f32 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
+
     #[inline(always)]
     pub fn as_rgb(&self) -> Rgb {
         let intensity = Interval::new(0.000, 0.999);
@@ -0,0 +1,147 @@
+use std::{f32, path::Path};
+
+use crate::{
+    background::Background, color::Color, ray::Ray, sampling::Distribution2D, vector::Vec3,
+};
+
+use anyhow::{Result, bail};
+
+// lat-long (equirectangular) environment map lighting the scene from infinitely far away
+// row 0 of the image is straight up (+y), and u runs around the y axis like on a Sphere
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    rotation: f32,  // rotation about the up axis, in radians
+    intensity: f32, // multiplier applied to every texel
+    distribution: Distribution2D,
+}
+
+impl EnvironmentMap {
+    // loads a Radiance .hdr (or any other format `image` can read) from disk
+    pub fn open(path: impl AsRef<Path>, rotation_degrees: f32, intensity: f32) -> Result<Self> {
+        let img = image::open(path)?.into_rgb32f();
+        let (width, height) = (img.width() as usize, img.height() as usize);
+        if width == 0 || height == 0 {
+            bail!("environment map has no pixels");
+        }
+
+        let pixels = img
+            .pixels()
+            .map(|px| Color::new(px.0[0], px.0[1], px.0[2]))
+            .collect();
+
+        Ok(Self::new(
+            width,
+            height,
+            pixels,
+            rotation_degrees,
+            intensity,
+        ))
+    }
+
+    pub fn new(
+        width: usize,
+        height: usize,
+        pixels: Vec<Color>,
+        rotation_degrees: f32,
+        intensity: f32,
+    ) -> Self {
+        // weight each texel by its luminance and by sin(theta) to account for
+        // rows near the poles covering a smaller solid angle
+        let mut func = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin_theta = (f32::consts::PI * (y as f32 + 0.5) / height as f32).sin();
+            for x in 0..width {
+                func.push(pixels[y * width + x].luminance() * sin_theta);
+            }
+        }
+
+        let distribution = Distribution2D::new(&func, width, height);
+
+        Self {
+            width,
+            height,
+            pixels,
+            rotation: rotation_degrees.to_radians(),
+            intensity,
+            distribution,
+        }
+    }
+
+    #[inline(always)]
+    fn rotate(&self, dir: &Vec3, angle: f32) -> Vec3 {
+        let (sin, cos) = angle.sin_cos();
+        Vec3::new(cos * dir.x + sin * dir.z, dir.y, -sin * dir.x + cos * dir.z)
+    }
+
+    // world space direction to image (u, v) in [0, 1)^2
+    #[inline(always)]
+    fn dir_to_uv(&self, dir: &Vec3) -> (f32, f32) {
+        let d = self.rotate(&dir.unit_vec(), -self.rotation);
+        let theta = d.y.clamp(-1., 1.).acos();
+        let phi = (-d.z).atan2(d.x) + f32::consts::PI;
+
+        (phi / (2. * f32::consts::PI), theta / f32::consts::PI)
+    }
+
+    // image (u, v) to a world space unit direction
+    #[inline(always)]
+    fn uv_to_dir(&self, u: f32, v: f32) -> Vec3 {
+        let theta = v * f32::consts::PI;
+        let phi = u * 2. * f32::consts::PI - f32::consts::PI;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+
+        let d = Vec3::new(sin_theta * phi.cos(), cos_theta, -sin_theta * phi.sin());
+        self.rotate(&d, self.rotation)
+    }
+
+    #[inline(always)]
+    fn lookup(&self, u: f32, v: f32) -> Color {
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+
+        self.intensity * self.pixels[y * self.width + x]
+    }
+}
+
+impl Background for EnvironmentMap {
+    #[inline(always)]
+    fn value(&self, r: &Ray) -> Color {
+        let (u, v) = self.dir_to_uv(&r.dir);
+        self.lookup(u, v)
+    }
+
+    #[inline(always)]
+    fn can_sample(&self) -> bool {
+        true
+    }
+
+    // picks a direction towards the environment in proportion to its brightness,
+    // returning it with the radiance arriving from it and its solid angle density
+    fn sample(&self) -> (Vec3, Color, f32) {
+        let ((u, v), pdf_uv) = self
+            .distribution
+            .sample_continuous(fastrand::f32(), fastrand::f32());
+
+        let sin_theta = (v * f32::consts::PI).sin();
+        let pdf = if sin_theta > 0. {
+            pdf_uv / (2. * f32::consts::PI * f32::consts::PI * sin_theta)
+        } else {
+            0.
+        };
+
+        (self.uv_to_dir(u, v), self.lookup(u, v), pdf)
+    }
+
+    // solid angle density of `sample` returning `dir`
+    fn pdf(&self, dir: &Vec3) -> f32 {
+        let (u, v) = self.dir_to_uv(dir);
+        let sin_theta = (v * f32::consts::PI).sin();
+        if sin_theta <= 0. {
+            return 0.;
+        }
+
+        self.distribution.pdf(u, v) / (2. * f32::consts::PI * f32::consts::PI * sin_theta)
+    }
+}
@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use crate::{
     aabb::Aabb,
+    color::Color,
+    light::LightBounds,
     material::Material,
     ray::{FaceNormal, Interval, Ray},
     vector::{Point3, Vec3},
@@ -12,6 +14,9 @@ pub struct HitRecord {
     pub t: f32,
     pub p: Point3,
     pub face_normal: FaceNormal,
+    pub u: f32, // surface coordinates of the hit point
+    pub v: f32,
+    pub vertex_color: Option<Color>, // interpolated color, for meshes with per-vertex colors
     pub mat: Arc<dyn Material + Sync + Send>,
 }
 
@@ -37,12 +42,17 @@ impl HitRecord {
         t: f32,
         p: Point3,
         face_normal: FaceNormal,
+        u: f32,
+        v: f32,
         mat: Arc<dyn Material + Sync + Send>,
     ) -> Self {
         Self {
             t,
             p,
             face_normal,
+            u,
+            v,
+            vertex_color: None,
             mat,
         }
     }
@@ -50,7 +60,44 @@ impl HitRecord {
 
 pub trait Hittable {
     fn hit(&self, r: &Ray, int: &Interval) -> Option<HitRecord>;
-    fn bounding_box(&self) -> &Aabb;
+    fn bounding_box(&self) -> Aabb;
+
+    // solid angle density of `random` picking the direction of `r` from its origin,
+    // only shapes that can be sampled as area lights implement these two
+    #[inline(always)]
+    fn pdf_value(&self, _r: &Ray) -> f32 {
+        0.
+    }
+
+    // direction from `origin` towards a random point on the surface at `time`
+    #[inline(always)]
+    fn random(&self, _origin: &Point3, _time: f32) -> Vec3 {
+        Vec3::new(1., 0., 0.)
+    }
+
+    // whether this object is itself an emitter that should be sampled as an area light
+    #[inline(always)]
+    fn is_light(&self) -> bool {
+        false
+    }
+
+    // bounds for the light tree, for the shapes `is_light` can be true for
+    #[inline(always)]
+    fn light_bounds(&self) -> Option<LightBounds> {
+        None
+    }
+
+    // adds the area lights inside an aggregate of other objects
+    fn collect_lights(&self, _lights: &mut HittableList) {}
+}
+
+// adds `object` to `lights` if it is an area light, otherwise the area lights inside it
+pub fn collect_lights(object: &Arc<dyn Hittable + Sync + Send>, lights: &mut HittableList) {
+    if object.is_light() {
+        lights.add(object.clone());
+    } else {
+        object.collect_lights(lights);
+    }
 }
 
 pub struct HittableList {
@@ -67,7 +114,7 @@ impl HittableList {
     }
 
     pub fn add(&mut self, object: Arc<dyn Hittable + Sync + Send>) {
-        self.bbox = Aabb::enclose(&self.bbox, object.bounding_box());
+        self.bbox = Aabb::enclose(&self.bbox, &object.bounding_box());
         self.objects.push(object);
     }
 }
@@ -91,7 +138,33 @@ impl Hittable for HittableList {
     }
 
     #[inline(always)]
-    fn bounding_box(&self) -> &Aabb {
-        &self.bbox
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    // the objects are picked uniformly, so the density is the average of theirs
+    #[inline(always)]
+    fn pdf_value(&self, r: &Ray) -> f32 {
+        if self.objects.is_empty() {
+            return 0.;
+        }
+
+        let sum: f32 = self.objects.iter().map(|o| o.pdf_value(r)).sum();
+        sum / self.objects.len() as f32
+    }
+
+    #[inline(always)]
+    fn random(&self, origin: &Point3, time: f32) -> Vec3 {
+        if self.objects.is_empty() {
+            return Vec3::new(1., 0., 0.);
+        }
+
+        self.objects[fastrand::usize(..self.objects.len())].random(origin, time)
+    }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        for object in &self.objects {
+            collect_lights(object, lights);
+        }
     }
 }
@@ -0,0 +1,762 @@
+use std::{f32, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    background::Background,
+    color::Color,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    pdf::{HittablePdf, Pdf},
+    ray::{FaceNormal, Interval, Ray},
+    vector::{Onb, Point3, Vec3},
+};
+
+use anyhow::{Result, bail};
+
+// incident light picked at a shading point
+pub struct LightSample {
+    pub dir: Vec3,       // unit direction from the shading point towards the light
+    pub dist: f32,       // distance to the light along `dir`, infinite for the background
+    pub radiance: Color, // radiance arriving along -dir
+    pub pdf: f32,        // density of picking `dir`, over solid angle, 1 for delta lights
+}
+
+// something the camera can sample directly instead of waiting for a path to hit it
+pub trait Light {
+    fn sample(&self, p: &Point3, time: f32) -> Option<LightSample>;
+
+    // solid angle density of `sample` picking the direction of `r` from its origin, where
+    // `t` is the first hit of `r` in the scene (infinity if it escapes): the sample only
+    // reaches the origin if the light is what is hit there
+    fn pdf(&self, r: &Ray, t: f32) -> f32;
+
+    // lights that are not part of the scene geometry can only be reached by light samples,
+    // which then need no weighting against BSDF sampling
+    #[inline(always)]
+    fn can_be_hit(&self) -> bool {
+        true
+    }
+
+    // where the light is and which way it shines, None for lights infinitely far away
+    #[inline(always)]
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+
+    // emitted power as a luminance, which lights are picked in proportion to, lights
+    // infinitely far away give what falls on a disk `scene_radius` across
+    #[inline(always)]
+    fn power(&self, _scene_radius: f32) -> f32 {
+        self.bounds().map_or(0., |bounds| bounds.phi)
+    }
+}
+
+// conservative bounds on a light's position, the directions it shines in and its power,
+// for the light tree to estimate how much it gives a point without sampling it
+#[derive(Clone)]
+pub struct LightBounds {
+    pub bbox: Aabb,
+    pub phi: f32,         // emitted power, as a luminance
+    pub w: Vec3,          // unit axis of the cone of surface normals
+    pub cos_theta_o: f32, // spread of the normals around `w`
+    pub cos_theta_e: f32, // how far from its normal light still leaves, pi/2 for diffuse
+    pub two_sided: bool,  // whether light also leaves against the normals
+}
+
+impl LightBounds {
+    #[inline(always)]
+    pub fn new(
+        bbox: Aabb,
+        phi: f32,
+        w: Vec3,
+        cos_theta_o: f32,
+        cos_theta_e: f32,
+        two_sided: bool,
+    ) -> Self {
+        Self {
+            bbox,
+            phi,
+            w,
+            cos_theta_o,
+            cos_theta_e,
+            two_sided,
+        }
+    }
+
+    // bounds of both lights together, as the light tree merges its children
+    pub fn union(a: &Self, b: &Self) -> Self {
+        if a.phi <= 0. {
+            return b.clone();
+        }
+        if b.phi <= 0. {
+            return a.clone();
+        }
+
+        let (w, cos_theta_o) = cone_union(&a.w, a.cos_theta_o, &b.w, b.cos_theta_o);
+
+        Self {
+            bbox: Aabb::enclose(&a.bbox, &b.bbox),
+            phi: a.phi + b.phi,
+            w,
+            cos_theta_o,
+            cos_theta_e: a.cos_theta_e.min(b.cos_theta_e),
+            two_sided: a.two_sided || b.two_sided,
+        }
+    }
+
+    // estimated light reaching `p`, only 0 where none of the lights inside can reach it,
+    // after Conty Estevez and Kulla's importance with the closest possible angles
+    pub fn importance(&self, p: &Point3) -> f32 {
+        let to_p = *p - self.bbox.centre();
+        let dist_squared = to_p.len_squared();
+
+        // keep points inside the box from blowing up
+        let radius = self.bbox.diagonal() / 2.;
+        let d2 = dist_squared.max(radius);
+
+        let mut cos_w = if dist_squared > 0. {
+            self.w.dot(&to_p) / dist_squared.sqrt()
+        } else {
+            1.
+        };
+        if self.two_sided {
+            cos_w = cos_w.abs();
+        }
+        let sin_w = (1. - cos_w * cos_w).max(0.).sqrt();
+
+        // half angle the box's bounding sphere covers as seen from p
+        let cos_b = if dist_squared < radius * radius {
+            -1.
+        } else {
+            (1. - radius * radius / dist_squared).max(0.).sqrt()
+        };
+        let sin_b = (1. - cos_b * cos_b).max(0.).sqrt();
+
+        // smallest angle between the normals and the direction to p, then narrowed by
+        // the size of the box
+        let sin_o = (1. - self.cos_theta_o * self.cos_theta_o).max(0.).sqrt();
+        let (sin_x, cos_x) = angle_sub_clamped(sin_w, cos_w, sin_o, self.cos_theta_o);
+        let (_, cos_p) = angle_sub_clamped(sin_x, cos_x, sin_b, cos_b);
+
+        if cos_p <= self.cos_theta_e {
+            return 0.;
+        }
+
+        self.phi * cos_p / d2
+    }
+}
+
+// sine and cosine of max(0, a - b), from those of the angles a and b
+#[inline(always)]
+fn angle_sub_clamped(sin_a: f32, cos_a: f32, sin_b: f32, cos_b: f32) -> (f32, f32) {
+    if cos_a > cos_b {
+        return (0., 1.);
+    }
+
+    (sin_a * cos_b - cos_a * sin_b, cos_a * cos_b + sin_a * sin_b)
+}
+
+// smallest cone around both cones, given as unit axes and cosines of their half angles
+fn cone_union(wa: &Vec3, cos_a: f32, wb: &Vec3, cos_b: f32) -> (Vec3, f32) {
+    let theta_a = cos_a.clamp(-1., 1.).acos();
+    let theta_b = cos_b.clamp(-1., 1.).acos();
+    let theta_d = wa.dot(wb).clamp(-1., 1.).acos();
+
+    // one cone already holds the other
+    if (theta_d + theta_b).min(f32::consts::PI) <= theta_a {
+        return (*wa, cos_a);
+    }
+    if (theta_d + theta_a).min(f32::consts::PI) <= theta_b {
+        return (*wb, cos_b);
+    }
+
+    let theta_o = (theta_a + theta_d + theta_b) / 2.;
+    let axis = wa.cross(wb);
+    if theta_o >= f32::consts::PI || axis.len_squared() == 0. {
+        return (*wa, -1.);
+    }
+
+    // turn wa towards wb until the cone reaches the far side of both
+    let k = axis.unit_vec();
+    let (sin_r, cos_r) = (theta_o - theta_a).sin_cos();
+    let w = cos_r * *wa + sin_r * k.cross(wa) + ((1. - cos_r) * k.dot(wa)) * k;
+
+    (w.unit_vec(), theta_o.cos())
+}
+
+// average luminance `mat` gives off at a few points of a surface, as (point, outward
+// normal, u, v), for shapes to estimate their power as lights
+pub fn mean_emission(
+    mat: &Arc<dyn Material + Sync + Send>,
+    points: &[(Point3, Vec3, f32, f32)],
+) -> f32 {
+    if points.is_empty() {
+        return 0.;
+    }
+
+    let sum: f32 = points
+        .iter()
+        .map(|(p, n, u, v)| {
+            let r = Ray::new(*p + *n, -*n, 0.);
+            let rec = HitRecord::new(1., *p, FaceNormal::Front(*n), *u, *v, mat.clone());
+            mat.emitted(&r, &rec).luminance()
+        })
+        .sum();
+
+    sum / points.len() as f32
+}
+
+// emissive surface, sampled through a HittablePdf towards its shape
+pub struct AreaLight {
+    pub shape: Arc<dyn Hittable + Sync + Send>,
+}
+
+impl AreaLight {
+    #[inline(always)]
+    pub fn new(shape: Arc<dyn Hittable + Sync + Send>) -> Self {
+        Self { shape }
+    }
+}
+
+impl Light for AreaLight {
+    #[inline(always)]
+    fn sample(&self, p: &Point3, time: f32) -> Option<LightSample> {
+        let dirs = HittablePdf::new(self.shape.as_ref(), p, time);
+        let r = Ray::new(*p, dirs.generate(), time);
+        let rec = self.shape.hit(&r, &Interval::new(0.001, f32::INFINITY))?;
+
+        let pdf = dirs.value(&r.dir);
+        if pdf <= 0. {
+            return None;
+        }
+
+        let len = r.dir.len();
+        Some(LightSample {
+            dir: r.dir / len,
+            dist: rec.t * len,
+            radiance: rec.mat.emitted(&r, &rec),
+            pdf,
+        })
+    }
+
+    #[inline(always)]
+    fn pdf(&self, r: &Ray, t: f32) -> f32 {
+        let first = self.shape.hit(r, &Interval::new(0.001, t + 0.001));
+        if first.is_none() {
+            return 0.;
+        }
+
+        self.shape.pdf_value(r)
+    }
+
+    #[inline(always)]
+    fn bounds(&self) -> Option<LightBounds> {
+        self.shape.light_bounds()
+    }
+}
+
+// a background that can be importance sampled, like an environment map
+pub struct EnvironmentLight {
+    pub background: Arc<dyn Background + Sync + Send>,
+    mean_luminance: f32, // over all directions
+}
+
+impl EnvironmentLight {
+    pub fn new(background: Arc<dyn Background + Sync + Send>) -> Self {
+        // average over directions spread evenly on a Fibonacci spiral
+        let n = 1024;
+        let golden_angle = f32::consts::PI * (3. - 5f32.sqrt());
+        let sum: f32 = (0..n)
+            .map(|i| {
+                let y = 1. - (2 * i + 1) as f32 / n as f32;
+                let r = (1. - y * y).sqrt();
+                let phi = golden_angle * i as f32;
+                let dir = Vec3::new(r * phi.cos(), y, r * phi.sin());
+                background
+                    .value(&Ray::new(Point3::zero(), dir, 0.))
+                    .luminance()
+            })
+            .sum();
+
+        Self {
+            background,
+            mean_luminance: sum / n as f32,
+        }
+    }
+}
+
+impl Light for EnvironmentLight {
+    #[inline(always)]
+    fn sample(&self, _p: &Point3, _time: f32) -> Option<LightSample> {
+        let (dir, radiance, pdf) = self.background.sample();
+        if pdf <= 0. {
+            return None;
+        }
+
+        Some(LightSample {
+            dir,
+            dist: f32::INFINITY,
+            radiance,
+            pdf,
+        })
+    }
+
+    #[inline(always)]
+    fn pdf(&self, r: &Ray, t: f32) -> f32 {
+        if t.is_finite() {
+            return 0.;
+        }
+
+        self.background.pdf(&r.dir)
+    }
+
+    #[inline(always)]
+    fn power(&self, scene_radius: f32) -> f32 {
+        f32::consts::PI * f32::consts::PI * scene_radius * scene_radius * self.mean_luminance
+    }
+}
+
+// angular intensity distribution of a luminaire in candela, from type C photometry:
+// vertical angles go from the nadir (0) to straight up (180) and horizontal angles turn
+// counterclockwise around the nadir, seen from above, from the 0 degree plane
+pub struct IesProfile {
+    vertical: Vec<f32>,   // degrees, increasing
+    horizontal: Vec<f32>, // degrees, increasing
+    candela: Vec<f32>,    // one row of `vertical.len()` values per horizontal angle
+    flux: f32,            // intensity integrated over all directions, in lumens
+}
+
+impl IesProfile {
+    // the angles have to be finite and increasing, and `candela` to hold a row of finite,
+    // non-negative values per horizontal angle
+    pub fn new(vertical: Vec<f32>, horizontal: Vec<f32>, candela: Vec<f32>) -> Result<Self> {
+        for (angles, what) in [(&vertical, "vertical"), (&horizontal, "horizontal")] {
+            if angles.is_empty() {
+                bail!("needs at least one {what} angle");
+            }
+            if angles.iter().any(|a| !a.is_finite()) {
+                bail!("{what} angles must be finite");
+            }
+            if angles.windows(2).any(|w| w[1] <= w[0]) {
+                bail!("{what} angles must be increasing");
+            }
+        }
+
+        let expected = vertical.len().checked_mul(horizontal.len());
+        if expected != Some(candela.len()) {
+            bail!(
+                "needs a candela value for each of the {} by {} angles, found {}",
+                vertical.len(),
+                horizontal.len(),
+                candela.len()
+            );
+        }
+        if candela.iter().any(|c| !c.is_finite() || *c < 0.) {
+            bail!("candela values must be finite and not negative");
+        }
+
+        let mut profile = Self {
+            vertical,
+            horizontal,
+            candela,
+            flux: 0.,
+        };
+
+        // midpoint rule over 1 degree by 2 degree patches
+        let (n_gamma, n_c) = (180, 180);
+        let d_gamma = f32::consts::PI / n_gamma as f32;
+        let d_c = 2. * f32::consts::PI / n_c as f32;
+        let mut flux = 0.;
+        for i in 0..n_gamma {
+            let gamma = (i as f32 + 0.5) * d_gamma;
+            for j in 0..n_c {
+                let c = (j as f32 + 0.5) * d_c;
+                flux += profile.candela(gamma.to_degrees(), c.to_degrees()) * gamma.sin();
+            }
+        }
+        profile.flux = flux * d_gamma * d_c;
+
+        Ok(profile)
+    }
+
+    #[inline(always)]
+    pub fn flux(&self) -> f32 {
+        self.flux
+    }
+
+    // intensity towards the unit direction `w`, given in a frame with x towards the
+    // 0 degree plane, y towards the 90 degree plane and z along the nadir
+    #[inline(always)]
+    pub fn intensity(&self, w: &Vec3) -> f32 {
+        let gamma = w.z.clamp(-1., 1.).acos().to_degrees();
+        let c = w.y.atan2(w.x).to_degrees();
+
+        self.candela(gamma, c)
+    }
+
+    // bilinear between the measured angles, nothing outside the vertical ones, and the
+    // horizontal angles a file leaves out filled in by the symmetry it implies
+    pub fn candela(&self, gamma: f32, c: f32) -> f32 {
+        let Some((i, s)) = Self::segment(&self.vertical, gamma) else {
+            return 0.;
+        };
+
+        let first = self.horizontal[0];
+        let last = self.horizontal[self.horizontal.len() - 1];
+        let mut c = c.rem_euclid(360.);
+        if first == 0. && last == 90. {
+            // the same in every quadrant
+            if c > 180. {
+                c = 360. - c;
+            }
+            if c > 90. {
+                c = 180. - c;
+            }
+        } else if first == 0. && last == 180. {
+            // mirrored across the 0-180 plane
+            if c > 180. {
+                c = 360. - c;
+            }
+        } else if first == 90. && last == 270. {
+            // mirrored across the 90-270 plane
+            if c < 90. {
+                c = 180. - c;
+            } else if c > 270. {
+                c = 540. - c;
+            }
+        }
+
+        let n = self.vertical.len();
+        let row = |j: usize| {
+            let r = &self.candela[j * n..(j + 1) * n];
+            let next = if s > 0. { r[i + 1] } else { r[i] };
+            r[i] + s * (next - r[i])
+        };
+
+        if self.horizontal.len() == 1 {
+            return row(0);
+        }
+
+        // a full turn may stop short of 360, wrap from the last angle back to the first
+        if first == 0. && c > last {
+            let t = (c - last) / (360. - last);
+            return row(self.horizontal.len() - 1) * (1. - t) + row(0) * t;
+        }
+
+        match Self::segment(&self.horizontal, c) {
+            Some((j, t)) if t > 0. => row(j) * (1. - t) + row(j + 1) * t,
+            Some((j, _)) => row(j),
+            None => 0.,
+        }
+    }
+
+    // index of the angle at or below `x` and how far `x` is towards the next one
+    #[inline(always)]
+    fn segment(angles: &[f32], x: f32) -> Option<(usize, f32)> {
+        let last = angles.len() - 1;
+        if x < angles[0] || x > angles[last] {
+            return None;
+        }
+
+        let i = (angles.partition_point(|&a| a <= x).max(1) - 1).min(last);
+        if i == last {
+            return Some((i, 0.));
+        }
+
+        let t = (x - angles[i]) / (angles[i + 1] - angles[i]);
+        Some((i, t))
+    }
+}
+
+// frame for looking up a profile, see IesProfile::intensity
+#[inline(always)]
+fn profile_frame(nadir: &Vec3, c0: &Vec3) -> Onb {
+    let w = nadir.unit_vec();
+    let u = *c0 - c0.dot(&w) * w;
+    let u = if u.len_squared() > 1e-12 {
+        u.unit_vec()
+    } else {
+        Onb::new(&w).u
+    };
+
+    Onb {
+        u,
+        v: u.cross(&w),
+        w,
+    }
+}
+
+// shines `intensity` equally in all directions from a single point, falling off with the
+// square of the distance, or shaped by a photometric profile
+pub struct PointLight {
+    pub position: Point3,
+    pub intensity: Color, // scales the candela of the profile when there is one
+    profile: Option<(Arc<IesProfile>, Onb)>,
+}
+
+#[allow(unused)]
+impl PointLight {
+    #[inline(always)]
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+            profile: None,
+        }
+    }
+
+    // a luminaire pointing its nadir along `nadir`, with its 0 degree horizontal plane
+    // towards `c0`
+    pub fn set_profile(&mut self, profile: Arc<IesProfile>, nadir: Vec3, c0: Vec3) {
+        self.profile = Some((profile, profile_frame(&nadir, &c0)));
+    }
+
+    // intensity sent along the unit direction `w` leaving the light
+    #[inline(always)]
+    fn intensity_towards(&self, w: &Vec3) -> Color {
+        match &self.profile {
+            Some((profile, frame)) => profile.intensity(&frame.local(w)) * self.intensity,
+            None => self.intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    #[inline(always)]
+    fn sample(&self, p: &Point3, _time: f32) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let dist = to_light.len();
+        if dist <= 0. {
+            return None;
+        }
+
+        let dir = to_light / dist;
+        Some(LightSample {
+            dir,
+            dist,
+            radiance: self.intensity_towards(&-dir) / (dist * dist),
+            pdf: 1.,
+        })
+    }
+
+    #[inline(always)]
+    fn pdf(&self, _r: &Ray, _t: f32) -> f32 {
+        0.
+    }
+
+    #[inline(always)]
+    fn can_be_hit(&self) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    fn bounds(&self) -> Option<LightBounds> {
+        let flux = match &self.profile {
+            Some((profile, _)) => profile.flux(),
+            None => 4. * f32::consts::PI,
+        };
+
+        Some(LightBounds::new(
+            Aabb::new(&self.position, &self.position),
+            flux * self.intensity.luminance(),
+            Vec3::new(0., 0., 1.),
+            -1.,
+            0.,
+            false,
+        ))
+    }
+}
+
+// point light restricted to a cone, at full intensity inside `inner_angle` from its axis
+// and fading smoothly to nothing at `outer_angle`, or aimed along the axis and shaped by
+// a photometric profile instead of the cone
+pub struct SpotLight {
+    pub position: Point3,
+    pub dir: Vec3,        // unit axis of the cone
+    pub intensity: Color, // scales the candela of the profile when there is one
+    cos_inner: f32,
+    cos_outer: f32,
+    profile: Option<(Arc<IesProfile>, Onb)>,
+}
+
+#[allow(unused)]
+impl SpotLight {
+    // angles in degrees, measured from the axis to the edge of the cone
+    #[inline(always)]
+    pub fn new(
+        position: Point3,
+        look_at: Point3,
+        intensity: Color,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        let outer_angle = outer_angle.clamp(0., 180.);
+        let inner_angle = inner_angle.clamp(0., outer_angle);
+
+        Self {
+            position,
+            dir: (look_at - position).unit_vec(),
+            intensity,
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+            profile: None,
+        }
+    }
+
+    // a luminaire pointing its nadir along the axis of the cone, with its 0 degree
+    // horizontal plane towards `c0`, the cone's angles then no longer clip the light since
+    // the profile already holds the luminaire's own cutoff
+    pub fn set_profile(&mut self, profile: Arc<IesProfile>, c0: Vec3) {
+        self.profile = Some((profile, profile_frame(&self.dir, &c0)));
+    }
+
+    // fraction of the intensity sent along the unit direction `w` leaving the light
+    #[inline(always)]
+    fn falloff(&self, w: &Vec3) -> f32 {
+        let cos_theta = w.dot(&self.dir);
+        if cos_theta >= self.cos_inner {
+            return 1.;
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.;
+        }
+
+        let t = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3. - 2. * t)
+    }
+}
+
+impl Light for SpotLight {
+    #[inline(always)]
+    fn sample(&self, p: &Point3, _time: f32) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let dist = to_light.len();
+        if dist <= 0. {
+            return None;
+        }
+
+        let dir = to_light / dist;
+        let intensity = match &self.profile {
+            Some((profile, frame)) => profile.intensity(&frame.local(&-dir)) * self.intensity,
+            None => self.falloff(&-dir) * self.intensity,
+        };
+        if intensity.near_zero() {
+            return None;
+        }
+
+        Some(LightSample {
+            dir,
+            dist,
+            radiance: intensity / (dist * dist),
+            pdf: 1.,
+        })
+    }
+
+    #[inline(always)]
+    fn pdf(&self, _r: &Ray, _t: f32) -> f32 {
+        0.
+    }
+
+    #[inline(always)]
+    fn can_be_hit(&self) -> bool {
+        false
+    }
+
+    // the cone of full intensity, with light leaving up to the outer angle past it, or any
+    // direction for a profile
+    #[inline(always)]
+    fn bounds(&self) -> Option<LightBounds> {
+        if let Some((profile, _)) = &self.profile {
+            return Some(LightBounds::new(
+                Aabb::new(&self.position, &self.position),
+                profile.flux() * self.intensity.luminance(),
+                self.dir,
+                -1.,
+                0.,
+                false,
+            ));
+        }
+
+        // the share of the whole sphere the cone covers
+        let share = 0.5 * (1. - 0.5 * (self.cos_inner + self.cos_outer));
+        let phi = share * 4. * f32::consts::PI * self.intensity.luminance();
+        let theta_e = self.cos_outer.acos() - self.cos_inner.acos();
+
+        Some(LightBounds::new(
+            Aabb::new(&self.position, &self.position),
+            phi,
+            self.dir,
+            self.cos_inner,
+            theta_e.cos(),
+            false,
+        ))
+    }
+}
+
+// light from infinitely far away like the sun, arriving from `dir` with `irradiance` on a
+// surface facing it, spread over a disk `angular_diameter` degrees across for soft shadows
+pub struct DirectionalLight {
+    pub dir: Vec3, // unit direction towards the light
+    pub irradiance: Color,
+    cos_max: f32, // cosine of the disk's angular radius
+}
+
+#[allow(unused)]
+impl DirectionalLight {
+    #[inline(always)]
+    pub fn new(dir: Vec3, irradiance: Color, angular_diameter: f32) -> Self {
+        let radius = (angular_diameter / 2.).clamp(0., 90.);
+
+        Self {
+            dir: dir.unit_vec(),
+            irradiance,
+            cos_max: radius.to_radians().cos(),
+        }
+    }
+
+    // the sun as seen from the earth, about half a degree across
+    #[inline(always)]
+    pub fn sun(dir: Vec3, irradiance: Color) -> Self {
+        Self::new(dir, irradiance, 0.53)
+    }
+}
+
+impl Light for DirectionalLight {
+    #[inline(always)]
+    fn sample(&self, _p: &Point3, _time: f32) -> Option<LightSample> {
+        if self.cos_max >= 1. {
+            return Some(LightSample {
+                dir: self.dir,
+                dist: f32::INFINITY,
+                radiance: self.irradiance,
+                pdf: 1.,
+            });
+        }
+
+        // uniform over the disk, with the radiance that adds up to the irradiance over it
+        let solid_angle = 2. * f32::consts::PI * (1. - self.cos_max);
+        let dir = Onb::new(&self.dir).transform(&Vec3::random_in_cone(self.cos_max));
+
+        Some(LightSample {
+            dir,
+            dist: f32::INFINITY,
+            radiance: self.irradiance / solid_angle,
+            pdf: 1. / solid_angle,
+        })
+    }
+
+    #[inline(always)]
+    fn pdf(&self, _r: &Ray, _t: f32) -> f32 {
+        0.
+    }
+
+    #[inline(always)]
+    fn can_be_hit(&self) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    fn power(&self, scene_radius: f32) -> f32 {
+        f32::consts::PI * scene_radius * scene_radius * self.irradiance.luminance()
+    }
+}
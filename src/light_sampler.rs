@@ -0,0 +1,347 @@
+use std::{f32, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    light::{Light, LightBounds},
+    ray::{Interval, Ray},
+    sampling::AliasTable,
+    vector::Point3,
+};
+
+// picks which light a shading point takes a light sample from
+pub trait LightSampler {
+    // a light for a shading point at `p`, with the probability it was picked with
+    fn sample(&self, p: &Point3) -> Option<(&Arc<dyn Light + Sync + Send>, f32)>;
+
+    // density of picking a light from the origin of `r` and then the direction of `r`
+    // from it, summed over the lights that `r` reaches at its first hit `t`, see Light::pdf
+    fn pdf(&self, r: &Ray, t: f32) -> f32;
+}
+
+// how the camera picks a light for each light sample
+#[derive(Clone, Copy)]
+pub enum LightSampling {
+    Uniform,
+    Power, // in proportion to each light's power, from an alias table
+    Tree,  // down a tree of light bounds, by estimated contribution at the shading point
+}
+
+impl LightSampling {
+    pub fn build(
+        self,
+        lights: Vec<Arc<dyn Light + Sync + Send>>,
+        scene: &Aabb,
+    ) -> Box<dyn LightSampler + Sync + Send> {
+        match self {
+            LightSampling::Uniform => Box::new(UniformLightSampler::new(lights)),
+            LightSampling::Power => Box::new(PowerLightSampler::new(lights, scene)),
+            LightSampling::Tree => Box::new(LightTree::new(lights)),
+        }
+    }
+}
+
+pub struct UniformLightSampler {
+    lights: Vec<Arc<dyn Light + Sync + Send>>,
+    index: LightTree, // over the same lights, only to find the ones a ray reaches
+}
+
+impl UniformLightSampler {
+    #[inline(always)]
+    pub fn new(lights: Vec<Arc<dyn Light + Sync + Send>>) -> Self {
+        Self {
+            index: LightTree::new(lights.clone()),
+            lights,
+        }
+    }
+}
+
+impl LightSampler for UniformLightSampler {
+    #[inline(always)]
+    fn sample(&self, _p: &Point3) -> Option<(&Arc<dyn Light + Sync + Send>, f32)> {
+        if self.lights.is_empty() {
+            return None;
+        }
+
+        let light = &self.lights[fastrand::usize(..self.lights.len())];
+        Some((light, 1. / self.lights.len() as f32))
+    }
+
+    #[inline(always)]
+    fn pdf(&self, r: &Ray, t: f32) -> f32 {
+        if self.lights.is_empty() {
+            return 0.;
+        }
+
+        let mut sum = 0.;
+        self.index
+            .lights_along(r, t, &mut |i| sum += self.lights[i].pdf(r, t));
+        sum / self.lights.len() as f32
+    }
+}
+
+// the same choice everywhere, in proportion to each light's power
+pub struct PowerLightSampler {
+    lights: Vec<Arc<dyn Light + Sync + Send>>,
+    table: AliasTable,
+    index: LightTree, // over the same lights, only to find the ones a ray reaches
+}
+
+impl PowerLightSampler {
+    pub fn new(lights: Vec<Arc<dyn Light + Sync + Send>>, scene: &Aabb) -> Self {
+        let scene_radius = scene.diagonal() / 2.;
+        let powers: Vec<f32> = lights
+            .iter()
+            .map(|light| light.power(scene_radius))
+            .collect();
+
+        Self {
+            index: LightTree::new(lights.clone()),
+            lights,
+            table: AliasTable::new(&powers),
+        }
+    }
+}
+
+impl LightSampler for PowerLightSampler {
+    #[inline(always)]
+    fn sample(&self, _p: &Point3) -> Option<(&Arc<dyn Light + Sync + Send>, f32)> {
+        let (i, pmf) = self.table.sample()?;
+        Some((&self.lights[i], pmf))
+    }
+
+    #[inline(always)]
+    fn pdf(&self, r: &Ray, t: f32) -> f32 {
+        let mut pdf = 0.;
+        self.index.lights_along(r, t, &mut |i| {
+            pdf += self.table.pmf(i) * self.lights[i].pdf(r, t)
+        });
+        pdf
+    }
+}
+
+// binary tree over the bounds of the lights, walked down by the estimated contribution
+// of each child at the shading point, so near and bright lights get most samples even
+// among thousands; lights infinitely far away sit beside the tree and are each picked as
+// often as the whole tree
+pub struct LightTree {
+    lights: Vec<Arc<dyn Light + Sync + Send>>,
+    infinite: Vec<usize>, // indices of the lights without bounds
+    nodes: Vec<LightNode>,
+    root: Option<usize>,
+}
+
+enum LightNode {
+    Leaf {
+        bounds: LightBounds,
+        light: usize,
+    },
+    Interior {
+        bounds: LightBounds,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl LightNode {
+    #[inline(always)]
+    fn bounds(&self) -> &LightBounds {
+        match self {
+            LightNode::Leaf { bounds, .. } => bounds,
+            LightNode::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+impl LightTree {
+    pub fn new(lights: Vec<Arc<dyn Light + Sync + Send>>) -> Self {
+        let mut infinite = Vec::new();
+        let mut bounded = Vec::new();
+        for (i, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                // lights that give off nothing are never picked
+                Some(bounds) if bounds.phi > 0. => bounded.push((i, bounds)),
+                Some(_) => {}
+                None => infinite.push(i),
+            }
+        }
+
+        let mut nodes = Vec::with_capacity(2 * bounded.len());
+        let root = (!bounded.is_empty()).then(|| Self::build(&mut nodes, &mut bounded));
+
+        Self {
+            lights,
+            infinite,
+            nodes,
+            root,
+        }
+    }
+
+    // splits the lights in half along the axis their centres spread out the most,
+    // returning the index of the new node
+    fn build(nodes: &mut Vec<LightNode>, lights: &mut [(usize, LightBounds)]) -> usize {
+        if let [(light, bounds)] = lights {
+            nodes.push(LightNode::Leaf {
+                bounds: bounds.clone(),
+                light: *light,
+            });
+            return nodes.len() - 1;
+        }
+
+        let mut centres = Aabb::empty();
+        for (_, bounds) in lights.iter() {
+            let c = bounds.bbox.centre();
+            centres = Aabb::enclose(&centres, &Aabb::new(&c, &c));
+        }
+        let axis = centres.longest_axis();
+        lights.sort_unstable_by(|(_, a), (_, b)| {
+            let a = a.bbox.axis_interval(axis);
+            let b = b.bbox.axis_interval(axis);
+            (a.min + a.max).total_cmp(&(b.min + b.max))
+        });
+
+        let (left, right) = lights.split_at_mut(lights.len() / 2);
+        let left = Self::build(nodes, left);
+        let right = Self::build(nodes, right);
+
+        let bounds = LightBounds::union(nodes[left].bounds(), nodes[right].bounds());
+        nodes.push(LightNode::Interior {
+            bounds,
+            left,
+            right,
+        });
+        nodes.len() - 1
+    }
+
+    // chance of picking one of the infinite lights rather than going down the tree
+    #[inline(always)]
+    fn infinite_probability(&self) -> f32 {
+        let n = self.infinite.len() as f32;
+        let tree = if self.root.is_some() { 1. } else { 0. };
+
+        if n + tree > 0. { n / (n + tree) } else { 0. }
+    }
+
+    // chances of going left and right from an interior node, for a point at `p`
+    #[inline(always)]
+    fn child_probabilities(&self, left: usize, right: usize, p: &Point3) -> Option<(f32, f32)> {
+        let i_left = self.nodes[left].bounds().importance(p);
+        let i_right = self.nodes[right].bounds().importance(p);
+        let total = i_left + i_right;
+        if total <= 0. {
+            return None;
+        }
+
+        Some((i_left / total, i_right / total))
+    }
+
+    // density of the tree below `node` picking a light and the direction of `r` from it,
+    // skipping branches the ray misses before `t` since their lights cannot give it any density
+    fn node_pdf(&self, node: usize, r: &Ray, t: f32) -> f32 {
+        match &self.nodes[node] {
+            LightNode::Leaf { bounds, light } => {
+                if bounds.importance(&r.origin) > 0. {
+                    self.lights[*light].pdf(r, t)
+                } else {
+                    0.
+                }
+            }
+            LightNode::Interior {
+                bounds,
+                left,
+                right,
+            } => {
+                if !bounds.bbox.hit(r, &Interval::new(0.001, t + 0.001)) {
+                    return 0.;
+                }
+
+                let Some((p_left, p_right)) = self.child_probabilities(*left, *right, &r.origin)
+                else {
+                    return 0.;
+                };
+
+                let mut pdf = 0.;
+                if p_left > 0. {
+                    pdf += p_left * self.node_pdf(*left, r, t);
+                }
+                if p_right > 0. {
+                    pdf += p_right * self.node_pdf(*right, r, t);
+                }
+                pdf
+            }
+        }
+    }
+
+    // calls `f` with the index of every light that `r` may reach before `t`: the infinite
+    // lights, and the bounded ones whose boxes it passes through; lights that give off
+    // nothing are not in the tree, but reaching them adds no light anyway
+    fn lights_along(&self, r: &Ray, t: f32, f: &mut impl FnMut(usize)) {
+        self.infinite.iter().for_each(|&i| f(i));
+        if let Some(root) = self.root {
+            self.node_lights_along(root, r, &Interval::new(0.001, t + 0.001), f);
+        }
+    }
+
+    fn node_lights_along(&self, node: usize, r: &Ray, int: &Interval, f: &mut impl FnMut(usize)) {
+        let node = &self.nodes[node];
+        if !node.bounds().bbox.hit(r, int) {
+            return;
+        }
+
+        match node {
+            LightNode::Leaf { light, .. } => f(*light),
+            LightNode::Interior { left, right, .. } => {
+                self.node_lights_along(*left, r, int, f);
+                self.node_lights_along(*right, r, int, f);
+            }
+        }
+    }
+}
+
+impl LightSampler for LightTree {
+    #[inline(always)]
+    fn sample(&self, p: &Point3) -> Option<(&Arc<dyn Light + Sync + Send>, f32)> {
+        let p_infinite = self.infinite_probability();
+        if fastrand::f32() < p_infinite {
+            let i = self.infinite[fastrand::usize(..self.infinite.len())];
+            return Some((&self.lights[i], p_infinite / self.infinite.len() as f32));
+        }
+
+        let mut node = self.root?;
+        let mut pmf = 1. - p_infinite;
+        loop {
+            match &self.nodes[node] {
+                LightNode::Leaf { bounds, light } => {
+                    return (bounds.importance(p) > 0.).then(|| (&self.lights[*light], pmf));
+                }
+                LightNode::Interior { left, right, .. } => {
+                    let (p_left, p_right) = self.child_probabilities(*left, *right, p)?;
+                    if fastrand::f32() < p_left {
+                        node = *left;
+                        pmf *= p_left;
+                    } else {
+                        node = *right;
+                        pmf *= p_right;
+                    }
+                }
+            }
+        }
+    }
+
+    fn pdf(&self, r: &Ray, t: f32) -> f32 {
+        let p_infinite = self.infinite_probability();
+
+        let mut pdf = 0.;
+        if !self.infinite.is_empty() {
+            let pmf = p_infinite / self.infinite.len() as f32;
+            for &i in &self.infinite {
+                pdf += pmf * self.lights[i].pdf(r, t);
+            }
+        }
+
+        if let Some(root) = self.root {
+            pdf += (1. - p_infinite) * self.node_pdf(root, r, t);
+        }
+
+        pdf
+    }
+}
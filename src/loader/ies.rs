@@ -0,0 +1,164 @@
+use std::path::Path;
+
+use crate::light::IesProfile;
+
+use anyhow::{Context, Result, anyhow, bail};
+
+// Loads the candela distribution of an IES LM-63 photometric file, type C only. The
+// values are scaled by the file's candela multiplier and ballast factors, so they are the
+// luminaire's real intensities. Tilt data is read past but not applied.
+pub fn load_ies(path: impl AsRef<Path>) -> Result<IesProfile> {
+    let path = path.as_ref();
+    let text = std::fs::read(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+    // older files are often in a legacy code page rather than utf-8
+    let text = String::from_utf8_lossy(&text);
+
+    parse_ies(&text).with_context(|| format!("failed to load {}", path.display()))
+}
+
+fn parse_ies(text: &str) -> Result<IesProfile> {
+    // everything up to the TILT line is a version line and [KEYWORD] lines
+    let mut lines = text.lines().enumerate();
+    let tilt = loop {
+        let (_, line) = lines.next().ok_or_else(|| anyhow!("missing TILT line"))?;
+        if let Some(tilt) = line.trim().strip_prefix("TILT=") {
+            break tilt.trim().to_string();
+        }
+    };
+
+    // the rest is numbers, spread over lines however the writer liked
+    let mut numbers = Numbers {
+        tokens: lines
+            .flat_map(|(i, line)| {
+                line.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|t| !t.is_empty())
+                    .map(move |t| (i + 1, t))
+            })
+            .collect::<Vec<_>>()
+            .into_iter(),
+    };
+
+    if tilt == "INCLUDE" {
+        // lamp to luminaire geometry, then the angles and their multipliers
+        numbers.next("tilt geometry")?;
+        let pairs = numbers.count("number of tilt angles")?;
+        for _ in 0..2 * pairs {
+            numbers.next("tilt data")?;
+        }
+    }
+
+    let _lamps = numbers.next("number of lamps")?;
+    let _lumens_per_lamp = numbers.next("lumens per lamp")?;
+    let multiplier = numbers.next("candela multiplier")?;
+    let n_vertical = numbers.count("number of vertical angles")?;
+    let n_horizontal = numbers.count("number of horizontal angles")?;
+    let photometric_type = numbers.next("photometric type")?;
+    let _units = numbers.next("units type")?;
+    let _width = numbers.next("luminaire width")?;
+    let _length = numbers.next("luminaire length")?;
+    let _height = numbers.next("luminaire height")?;
+
+    let ballast_factor = numbers.next("ballast factor")?;
+    let ballast_lamp_factor = numbers.next("ballast-lamp photometric factor")?;
+    let _input_watts = numbers.next("input watts")?;
+
+    match photometric_type as i32 {
+        1 => {}
+        2 => bail!("type B photometry is not supported, only type C"),
+        3 => bail!("type A photometry is not supported, only type C"),
+        other => bail!("unknown photometric type {other}"),
+    }
+
+    if n_vertical == 0 || n_horizontal == 0 {
+        bail!("needs at least one vertical and one horizontal angle");
+    }
+
+    let vertical = numbers.angles(n_vertical, "vertical angle")?;
+    let horizontal = numbers.angles(n_horizontal, "horizontal angle")?;
+    if vertical[0] < 0. || vertical[n_vertical - 1] > 180. {
+        bail!("type C vertical angles must lie between 0 and 180 degrees");
+    }
+    if horizontal[0] < 0. || horizontal[n_horizontal - 1] > 360. {
+        bail!("type C horizontal angles must lie between 0 and 360 degrees");
+    }
+
+    let n_candela = n_vertical.checked_mul(n_horizontal).ok_or_else(|| {
+        anyhow!("{n_vertical} by {n_horizontal} angles is too many candela values")
+    })?;
+
+    let scale = multiplier * ballast_factor * ballast_lamp_factor;
+    let mut candela = Vec::new();
+    for _ in 0..n_candela {
+        let value = numbers.next("candela value")?;
+        if value < 0. {
+            bail!("negative candela value {value}");
+        }
+        candela.push(value * scale);
+    }
+
+    IesProfile::new(vertical, horizontal, candela)
+}
+
+// the numeric tokens after the TILT line, with the line each came from
+struct Numbers<'a> {
+    tokens: std::vec::IntoIter<(usize, &'a str)>,
+}
+
+impl Numbers<'_> {
+    fn next(&mut self, what: &str) -> Result<f32> {
+        let (line, token) = self
+            .tokens
+            .next()
+            .ok_or_else(|| anyhow!("file ends before the {what}"))?;
+
+        let value: f32 = token
+            .parse()
+            .with_context(|| format!("line {line}: invalid {what} `{token}`"))?;
+
+        // `parse` also takes "NaN" and "inf"
+        if !value.is_finite() {
+            bail!("line {line}: invalid {what} `{token}`");
+        }
+
+        Ok(value)
+    }
+
+    // a count of values still to come, which cannot be more than the tokens left
+    fn count(&mut self, what: &str) -> Result<usize> {
+        let value = self.next(what)?;
+        if value < 0. || value.fract() != 0. {
+            bail!("{what} must be a whole number, found {value}");
+        }
+        if value > self.remaining() as f32 {
+            bail!(
+                "{what} is {value} but the file only has {} numbers left",
+                self.remaining()
+            );
+        }
+
+        Ok(value as usize)
+    }
+
+    #[inline(always)]
+    fn remaining(&self) -> usize {
+        self.tokens.len()
+    }
+
+    // `n` angles in degrees, which must be increasing
+    fn angles(&mut self, n: usize, what: &str) -> Result<Vec<f32>> {
+        let mut angles = Vec::new();
+        for _ in 0..n {
+            let angle = self.next(what)?;
+            if angles.last().is_some_and(|&last| angle <= last) {
+                bail!(
+                    "{what}s must be increasing, found {angle} after {}",
+                    angles[angles.len() - 1]
+                );
+            }
+            angles.push(angle);
+        }
+
+        Ok(angles)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod ies;
+pub mod obj;
+pub mod ply;
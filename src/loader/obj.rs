@@ -0,0 +1,453 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    str::SplitWhitespace,
+    sync::Arc,
+};
+
+use crate::{
+    color::Color,
+    hittable::HittableList,
+    material::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
+    shapes::triangle::TriangleMesh,
+    texture::ImageTexture,
+    vector::{Point3, Vec3},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+// Loads a Wavefront .obj file and the .mtl libraries it references into a list of triangles.
+// Faces are split into one TriangleMesh per group and material, and `default_mat` is used
+// for faces that have no material or one that no .mtl library defines.
+pub fn load_obj(
+    path: impl AsRef<Path>,
+    default_mat: Arc<dyn Material + Sync + Send>,
+) -> Result<HittableList> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+    let mut parser = ObjParser::new(path.parent().unwrap_or(Path::new(".")), default_mat);
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        parser
+            .parse_line(&line)
+            .with_context(|| format!("{}:{}", path.display(), i + 1))?;
+    }
+
+    let mut list = HittableList::new();
+    for mesh in parser.meshes {
+        if mesh.indices.is_empty() {
+            continue;
+        }
+
+        for tri in mesh.build()?.into_hittable_list().objects {
+            list.add(tri);
+        }
+    }
+
+    if list.objects.is_empty() {
+        bail!("{}: no faces", path.display());
+    }
+
+    Ok(list)
+}
+
+// Loads the materials of a .mtl file, keyed by name.
+pub fn load_mtl(
+    path: impl AsRef<Path>,
+) -> Result<HashMap<String, Arc<dyn Material + Sync + Send>>> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line_no = i + 1;
+        let context = || format!("{}:{}", path.display(), line_no);
+
+        let mut tokens = strip_comment(&line).split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            let name = tokens
+                .next()
+                .ok_or_else(|| anyhow!("newmtl without a name"))
+                .with_context(context)?;
+
+            if let Some(mtl) = current.take() {
+                materials.insert(mtl.name.clone(), mtl.build().with_context(context)?);
+            }
+            current = Some(MtlMaterial::new(name));
+            continue;
+        }
+
+        let Some(mtl) = current.as_mut() else {
+            // statements before the first newmtl have nothing to apply to
+            continue;
+        };
+
+        mtl.parse_statement(keyword, tokens, dir)
+            .with_context(context)?;
+    }
+
+    if let Some(mtl) = current.take() {
+        materials.insert(mtl.name.clone(), mtl.build()?);
+    }
+
+    Ok(materials)
+}
+
+#[inline(always)]
+fn strip_comment(line: &str) -> &str {
+    line.split('#').next().unwrap_or("")
+}
+
+fn parse_floats<const N: usize>(tokens: &mut SplitWhitespace, required: usize) -> Result<[f32; N]> {
+    let mut values = [0.; N];
+
+    for (i, value) in values.iter_mut().enumerate() {
+        match tokens.next() {
+            Some(token) => {
+                *value = token
+                    .parse()
+                    .with_context(|| format!("invalid number `{token}`"))?
+            }
+            None if i < required => bail!("expected {required} numbers, found {i}"),
+            None => break,
+        }
+    }
+
+    Ok(values)
+}
+
+#[inline(always)]
+fn parse_color(tokens: &mut SplitWhitespace) -> Result<Color> {
+    let [r, g, b] = parse_floats::<3>(tokens, 3)?;
+    Ok(Color::new(r, g, b))
+}
+
+// OBJ indices are 1-based, negative ones count back from the last element defined so far
+fn resolve_index(token: &str, len: usize, kind: &str) -> Result<usize> {
+    let idx: i64 = token
+        .parse()
+        .with_context(|| format!("invalid {kind} index `{token}`"))?;
+
+    let resolved = match idx {
+        0 => bail!("{kind} index cannot be 0"),
+        i if i > 0 => i - 1,
+        i => len as i64 + i,
+    };
+
+    if resolved < 0 || resolved as usize >= len {
+        bail!("{kind} index {idx} is out of range, there are {len} so far");
+    }
+
+    Ok(resolved as usize)
+}
+
+// (position, uv, normal) indices of a face corner
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+// accumulates the faces of one group/material combination, deduplicating the
+// corner combinations into the single index buffer TriangleMesh expects
+struct MeshBuilder {
+    mat: Arc<dyn Material + Sync + Send>,
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f32, f32)>,
+    indices: Vec<[u32; 3]>,
+    vertex_map: HashMap<VertexKey, u32>,
+    missing_normals: bool,
+    missing_uvs: bool,
+}
+
+impl MeshBuilder {
+    fn new(mat: Arc<dyn Material + Sync + Send>) -> Self {
+        Self {
+            mat,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices: Vec::new(),
+            vertex_map: HashMap::new(),
+            missing_normals: false,
+            missing_uvs: false,
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        key: VertexKey,
+        positions: &[Point3],
+        uvs: &[(f32, f32)],
+        normals: &[Vec3],
+    ) -> u32 {
+        if let Some(&idx) = self.vertex_map.get(&key) {
+            return idx;
+        }
+
+        let (p, vt, vn) = key;
+        let idx = self.positions.len() as u32;
+
+        self.positions.push(positions[p]);
+        match vt {
+            Some(vt) => self.uvs.push(uvs[vt]),
+            None => {
+                self.missing_uvs = true;
+                self.uvs.push((0., 0.));
+            }
+        }
+        match vn {
+            Some(vn) => self.normals.push(normals[vn]),
+            None => {
+                self.missing_normals = true;
+                self.normals.push(Vec3::zero());
+            }
+        }
+
+        self.vertex_map.insert(key, idx);
+        idx
+    }
+
+    fn build(mut self) -> Result<TriangleMesh> {
+        // vertex attributes have to be all or nothing for a mesh
+        if self.missing_normals {
+            self.normals.clear();
+        }
+        if self.missing_uvs {
+            self.uvs.clear();
+        }
+
+        TriangleMesh::new(
+            self.positions,
+            self.normals,
+            self.uvs,
+            Vec::new(),
+            self.indices,
+            self.mat,
+        )
+    }
+}
+
+struct ObjParser {
+    dir: PathBuf,
+    positions: Vec<Point3>,
+    uvs: Vec<(f32, f32)>,
+    normals: Vec<Vec3>,
+    default_mat: Arc<dyn Material + Sync + Send>,
+    materials: HashMap<String, Arc<dyn Material + Sync + Send>>,
+    group: String,
+    material: Option<String>,
+    mesh_ids: HashMap<(String, Option<String>), usize>,
+    meshes: Vec<MeshBuilder>,
+}
+
+impl ObjParser {
+    fn new(dir: &Path, default_mat: Arc<dyn Material + Sync + Send>) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            default_mat,
+            materials: HashMap::new(),
+            group: String::new(),
+            material: None,
+            mesh_ids: HashMap::new(),
+            meshes: Vec::new(),
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let mut tokens = strip_comment(line).split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            return Ok(());
+        };
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats::<3>(&mut tokens, 3)?;
+                self.positions.push(Point3::new(x, y, z));
+            }
+            "vt" => {
+                let [u, v] = parse_floats::<2>(&mut tokens, 1)?;
+                self.uvs.push((u, v));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats::<3>(&mut tokens, 3)?;
+                let normal = Vec3::new(x, y, z);
+                if normal.len_squared() == 0. || !normal.len_squared().is_finite() {
+                    bail!("normal ({x}, {y}, {z}) has no direction");
+                }
+                self.normals.push(normal.unit_vec());
+            }
+            "f" => self.parse_face(tokens)?,
+            "g" | "o" => {
+                self.group = tokens.collect::<Vec<_>>().join(" ");
+            }
+            "usemtl" => {
+                let name = tokens
+                    .next()
+                    .ok_or_else(|| anyhow!("usemtl without a material name"))?;
+                // exporters often name materials their .mtl files never define, those faces
+                // get the default material
+                self.material = self.materials.contains_key(name).then(|| name.to_string());
+            }
+            "mtllib" => {
+                for lib in tokens {
+                    let materials = load_mtl(self.dir.join(lib))?;
+                    self.materials.extend(materials);
+                }
+            }
+            // smoothing groups, lines, points and everything else we do not render
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn parse_face(&mut self, tokens: SplitWhitespace) -> Result<()> {
+        let mut corners = Vec::new();
+
+        for corner in tokens {
+            let mut parts = corner.split('/');
+
+            let p = resolve_index(
+                parts.next().unwrap_or_default(),
+                self.positions.len(),
+                "vertex",
+            )?;
+            let vt = match parts.next() {
+                Some(s) if !s.is_empty() => Some(resolve_index(s, self.uvs.len(), "texture")?),
+                _ => None,
+            };
+            let vn = match parts.next() {
+                Some(s) if !s.is_empty() => Some(resolve_index(s, self.normals.len(), "normal")?),
+                _ => None,
+            };
+
+            corners.push((p, vt, vn));
+        }
+
+        if corners.len() < 3 {
+            bail!("face needs at least 3 vertices, found {}", corners.len());
+        }
+
+        let mesh_id = self.current_mesh();
+        let mesh = &mut self.meshes[mesh_id];
+        let (positions, uvs, normals) = (&self.positions, &self.uvs, &self.normals);
+
+        // triangulate the polygon as a fan around its first corner
+        let first = mesh.vertex(corners[0], positions, uvs, normals);
+        for pair in corners[1..].windows(2) {
+            let b = mesh.vertex(pair[0], positions, uvs, normals);
+            let c = mesh.vertex(pair[1], positions, uvs, normals);
+            mesh.indices.push([first, b, c]);
+        }
+
+        Ok(())
+    }
+
+    // index of the mesh collecting faces for the current group and material
+    fn current_mesh(&mut self) -> usize {
+        let key = (self.group.clone(), self.material.clone());
+        if let Some(&id) = self.mesh_ids.get(&key) {
+            return id;
+        }
+
+        let mat = match &self.material {
+            Some(name) => self.materials[name].clone(),
+            None => self.default_mat.clone(),
+        };
+
+        let id = self.meshes.len();
+        self.meshes.push(MeshBuilder::new(mat));
+        self.mesh_ids.insert(key, id);
+        id
+    }
+}
+
+// the subset of the MTL illumination model we can map onto the crate's materials
+struct MtlMaterial {
+    name: String,
+    diffuse: Color,               // Kd
+    specular: Color,              // Ks
+    shininess: f32,               // Ns
+    refract_idx: f32,             // Ni
+    emission: Color,              // Ke
+    dissolve: f32,                // d, or 1 - Tr
+    diffuse_map: Option<PathBuf>, // map_Kd
+}
+
+impl MtlMaterial {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::zero(),
+            shininess: 0.,
+            refract_idx: 1.5,
+            emission: Color::zero(),
+            dissolve: 1.,
+            diffuse_map: None,
+        }
+    }
+
+    fn parse_statement(
+        &mut self,
+        keyword: &str,
+        mut tokens: SplitWhitespace,
+        dir: &Path,
+    ) -> Result<()> {
+        match keyword {
+            "Kd" => self.diffuse = parse_color(&mut tokens)?,
+            "Ks" => self.specular = parse_color(&mut tokens)?,
+            "Ke" => self.emission = parse_color(&mut tokens)?,
+            "Ns" => [self.shininess] = parse_floats::<1>(&mut tokens, 1)?,
+            "Ni" => [self.refract_idx] = parse_floats::<1>(&mut tokens, 1)?,
+            "d" => [self.dissolve] = parse_floats::<1>(&mut tokens, 1)?,
+            "Tr" => {
+                let [tr] = parse_floats::<1>(&mut tokens, 1)?;
+                self.dissolve = 1. - tr;
+            }
+            "map_Kd" => {
+                // options like `-s 1 1 1` come before the file name
+                let file = tokens
+                    .last()
+                    .ok_or_else(|| anyhow!("map_Kd without a file name"))?;
+                self.diffuse_map = Some(dir.join(file));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn build(self) -> Result<Arc<dyn Material + Sync + Send>> {
+        let mat: Arc<dyn Material + Sync + Send> = if self.emission.luminance() > 0. {
+            Arc::new(DiffuseLight::new(self.emission))
+        } else if self.dissolve < 1. {
+            Arc::new(Dielectric::new(self.refract_idx))
+        } else if self.specular.luminance() > self.diffuse.luminance() {
+            // map the Phong exponent onto a roughness
+            let fuzz = (2. / (self.shininess + 2.)).sqrt().clamp(0., 1.);
+            Arc::new(Metal::new(self.specular, fuzz))
+        } else if let Some(map) = &self.diffuse_map {
+            let texture = ImageTexture::open(map)
+                .with_context(|| format!("failed to load texture {}", map.display()))?;
+            Arc::new(Lambertian::from_texture(Arc::new(texture)))
+        } else {
+            Arc::new(Lambertian::new(self.diffuse))
+        };
+
+        Ok(mat)
+    }
+}
@@ -0,0 +1,517 @@
+use std::{path::Path, str::SplitWhitespace, sync::Arc};
+
+use crate::{
+    color::Color,
+    material::Material,
+    shapes::triangle::TriangleMesh,
+    vector::{Point3, Vec3},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+// Loads the vertices and faces of an ascii or binary little endian .ply file into a mesh.
+// Normals, uvs and colors are picked up when the vertices have them, polygons are
+// triangulated as fans and any other element is skipped.
+pub fn load_ply(
+    path: impl AsRef<Path>,
+    mat: Arc<dyn Material + Sync + Send>,
+) -> Result<TriangleMesh> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+    parse_ply(&data, mat).with_context(|| format!("failed to load {}", path.display()))
+}
+
+fn parse_ply(data: &[u8], mat: Arc<dyn Material + Sync + Send>) -> Result<TriangleMesh> {
+    let (header, body) = split_header(data)?;
+    let header = Header::parse(header)?;
+
+    let mut reader: Box<dyn ValueReader> = match header.format {
+        Format::Ascii => Box::new(AsciiReader::new(body)?),
+        Format::BinaryLittleEndian => Box::new(BinaryReader::new(body)),
+    };
+
+    let mut mesh = MeshData::default();
+
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => mesh.read_vertices(element, reader.as_mut())?,
+            "face" => mesh.read_faces(element, reader.as_mut())?,
+            _ => {
+                for _ in 0..element.count {
+                    for prop in &element.properties {
+                        prop.skip(reader.as_mut())?;
+                    }
+                }
+            }
+        }
+    }
+
+    mesh.build(mat)
+}
+
+// header and body bytes, the header has to be ascii
+fn split_header(data: &[u8]) -> Result<(&str, &[u8])> {
+    const END: &[u8] = b"end_header";
+
+    let end = data
+        .windows(END.len())
+        .position(|w| w == END)
+        .ok_or_else(|| anyhow!("missing end_header"))?;
+
+    // the body starts after the newline that ends the header
+    let body_start = data[end..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map(|i| end + i + 1)
+        .unwrap_or(data.len());
+
+    let header = std::str::from_utf8(&data[..end]).context("header is not valid text")?;
+
+    Ok((header, &data[body_start..]))
+}
+
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+#[derive(Clone, Copy)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => bail!("unknown property type `{name}`"),
+        })
+    }
+
+    #[inline(always)]
+    const fn size(&self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    #[inline(always)]
+    const fn is_integer(&self) -> bool {
+        !matches!(self, Self::F32 | Self::F64)
+    }
+
+    // largest value of an integer type, which stands for full intensity in a color
+    #[inline(always)]
+    const fn max_value(&self) -> Option<f32> {
+        match self {
+            Self::I8 => Some(i8::MAX as f32),
+            Self::U8 => Some(u8::MAX as f32),
+            Self::I16 => Some(i16::MAX as f32),
+            Self::U16 => Some(u16::MAX as f32),
+            Self::I32 => Some(i32::MAX as f32),
+            Self::U32 => Some(u32::MAX as f32),
+            Self::F32 | Self::F64 => None,
+        }
+    }
+}
+
+enum Property {
+    Scalar {
+        name: String,
+        ty: ScalarType,
+    },
+    List {
+        name: String,
+        count_ty: ScalarType,
+        item_ty: ScalarType,
+    },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar { name, .. } | Property::List { name, .. } => name,
+        }
+    }
+
+    fn skip(&self, reader: &mut dyn ValueReader) -> Result<()> {
+        match self {
+            Property::Scalar { ty, .. } => {
+                reader.read(*ty)?;
+            }
+            Property::List {
+                count_ty, item_ty, ..
+            } => {
+                let count = reader.read(*count_ty)? as usize;
+                for _ in 0..count {
+                    reader.read(*item_ty)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    // position of the first scalar property with one of the given names
+    fn scalar(&self, names: &[&str]) -> Option<(usize, ScalarType)> {
+        self.properties
+            .iter()
+            .enumerate()
+            .find_map(|(i, p)| match p {
+                Property::Scalar { name, ty } if names.contains(&name.as_str()) => Some((i, *ty)),
+                _ => None,
+            })
+    }
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+impl Header {
+    fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate();
+
+        match lines.next() {
+            Some((_, line)) if line.trim() == "ply" => {}
+            _ => bail!("not a ply file"),
+        }
+
+        let mut format = None;
+        let mut elements: Vec<Element> = Vec::new();
+
+        for (i, line) in lines {
+            Self::parse_line(line, &mut format, &mut elements)
+                .with_context(|| format!("header line {}", i + 1))?;
+        }
+
+        let format = format.ok_or_else(|| anyhow!("missing format line"))?;
+
+        Ok(Self { format, elements })
+    }
+
+    fn parse_line(
+        line: &str,
+        format: &mut Option<Format>,
+        elements: &mut Vec<Element>,
+    ) -> Result<()> {
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("format") => {
+                *format = Some(match tokens.next() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some(other) => bail!("unsupported format `{other}`"),
+                    None => bail!("missing format"),
+                })
+            }
+            Some("element") => {
+                let name = tokens
+                    .next()
+                    .ok_or_else(|| anyhow!("missing element name"))?;
+                let count = next_token(&mut tokens)?
+                    .parse()
+                    .context("invalid element count")?;
+
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            Some("property") => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| anyhow!("property before any element"))?;
+
+                let ty = next_token(&mut tokens)?;
+                let property = if ty == "list" {
+                    let count_ty = ScalarType::parse(next_token(&mut tokens)?)?;
+                    let item_ty = ScalarType::parse(next_token(&mut tokens)?)?;
+                    Property::List {
+                        name: next_token(&mut tokens)?.to_string(),
+                        count_ty,
+                        item_ty,
+                    }
+                } else {
+                    Property::Scalar {
+                        ty: ScalarType::parse(ty)?,
+                        name: next_token(&mut tokens)?.to_string(),
+                    }
+                };
+
+                element.properties.push(property);
+            }
+            // comments, obj_info and blank lines
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[inline(always)]
+fn next_token<'a>(tokens: &mut SplitWhitespace<'a>) -> Result<&'a str> {
+    tokens
+        .next()
+        .ok_or_else(|| anyhow!("unexpected end of line"))
+}
+
+// reads the values of the body one at a time, whatever the encoding
+trait ValueReader {
+    fn read(&mut self, ty: ScalarType) -> Result<f64>;
+}
+
+struct AsciiReader<'a> {
+    tokens: SplitWhitespace<'a>,
+}
+
+impl<'a> AsciiReader<'a> {
+    fn new(body: &'a [u8]) -> Result<Self> {
+        let text = std::str::from_utf8(body).context("ascii body is not valid text")?;
+        Ok(Self {
+            tokens: text.split_whitespace(),
+        })
+    }
+}
+
+impl ValueReader for AsciiReader<'_> {
+    #[inline(always)]
+    fn read(&mut self, ty: ScalarType) -> Result<f64> {
+        let token = self
+            .tokens
+            .next()
+            .ok_or_else(|| anyhow!("unexpected end of data"))?;
+
+        if ty.is_integer() {
+            token
+                .parse::<i64>()
+                .map(|v| v as f64)
+                .with_context(|| format!("invalid integer `{token}`"))
+        } else {
+            token
+                .parse::<f64>()
+                .with_context(|| format!("invalid number `{token}`"))
+        }
+    }
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+}
+
+impl ValueReader for BinaryReader<'_> {
+    #[inline(always)]
+    fn read(&mut self, ty: ScalarType) -> Result<f64> {
+        let size = ty.size();
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + size)
+            .ok_or_else(|| anyhow!("unexpected end of data at byte {}", self.pos))?;
+        self.pos += size;
+
+        let mut buf = [0u8; 8];
+        buf[..size].copy_from_slice(bytes);
+
+        Ok(match ty {
+            ScalarType::I8 => bytes[0] as i8 as f64,
+            ScalarType::U8 => bytes[0] as f64,
+            ScalarType::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            ScalarType::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            ScalarType::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ScalarType::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ScalarType::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            ScalarType::F64 => f64::from_le_bytes(buf),
+        })
+    }
+}
+
+#[derive(Default)]
+struct MeshData {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f32, f32)>,
+    colors: Vec<Color>,
+    indices: Vec<[u32; 3]>,
+}
+
+impl MeshData {
+    fn read_vertices(&mut self, element: &Element, reader: &mut dyn ValueReader) -> Result<()> {
+        let position = [
+            element.scalar(&["x"]),
+            element.scalar(&["y"]),
+            element.scalar(&["z"]),
+        ];
+        let [Some((x, _)), Some((y, _)), Some((z, _))] = position else {
+            bail!("vertex element needs x, y and z properties");
+        };
+
+        let normal = match [
+            element.scalar(&["nx"]),
+            element.scalar(&["ny"]),
+            element.scalar(&["nz"]),
+        ] {
+            [Some((nx, _)), Some((ny, _)), Some((nz, _))] => Some([nx, ny, nz]),
+            _ => None,
+        };
+
+        let uv = match [
+            element.scalar(&["u", "s", "texture_u", "texture_s"]),
+            element.scalar(&["v", "t", "texture_v", "texture_t"]),
+        ] {
+            [Some((u, _)), Some((v, _))] => Some([u, v]),
+            _ => None,
+        };
+
+        let color = match [
+            element.scalar(&["red", "r", "diffuse_red"]),
+            element.scalar(&["green", "g", "diffuse_green"]),
+            element.scalar(&["blue", "b", "diffuse_blue"]),
+        ] {
+            [Some(r), Some(g), Some(b)] => Some([r, g, b]),
+            _ => None,
+        };
+
+        let mut values = vec![0.; element.properties.len()];
+
+        for _ in 0..element.count {
+            for (value, prop) in values.iter_mut().zip(&element.properties) {
+                match prop {
+                    Property::Scalar { ty, .. } => *value = reader.read(*ty)?,
+                    Property::List { .. } => prop.skip(reader)?,
+                }
+            }
+            let value = |idx: usize| values[idx] as f32;
+
+            self.positions
+                .push(Point3::new(value(x), value(y), value(z)));
+
+            if let Some([nx, ny, nz]) = normal {
+                self.normals
+                    .push(Vec3::new(value(nx), value(ny), value(nz)).unit_vec());
+            }
+
+            if let Some([u, v]) = uv {
+                self.uvs.push((value(u), value(v)));
+            }
+
+            if let Some([r, g, b]) = color {
+                // integer colors are gamma encoded over the range of their type, float ones
+                // are taken as linear
+                let channel = |(idx, ty): (usize, ScalarType)| match ty.max_value() {
+                    Some(max) => {
+                        let c = value(idx) / max;
+                        c * c
+                    }
+                    None => value(idx),
+                };
+                self.colors
+                    .push(Color::new(channel(r), channel(g), channel(b)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_faces(&mut self, element: &Element, reader: &mut dyn ValueReader) -> Result<()> {
+        let indices_prop = element
+            .properties
+            .iter()
+            .position(|p| matches!(p.name(), "vertex_indices" | "vertex_index"))
+            .ok_or_else(|| anyhow!("face element needs a vertex_indices list"))?;
+
+        let mut polygon = Vec::new();
+
+        for face in 0..element.count {
+            for (i, prop) in element.properties.iter().enumerate() {
+                match prop {
+                    Property::List {
+                        count_ty, item_ty, ..
+                    } if i == indices_prop => {
+                        let count = reader.read(*count_ty)? as usize;
+                        polygon.clear();
+                        for _ in 0..count {
+                            polygon.push(reader.read(*item_ty)? as i64);
+                        }
+                    }
+                    _ => prop.skip(reader)?,
+                }
+            }
+
+            if polygon.len() < 3 {
+                bail!("face {face} has fewer than 3 vertices");
+            }
+
+            if let Some(&idx) = polygon
+                .iter()
+                .find(|&&idx| idx < 0 || idx as usize >= self.positions.len())
+            {
+                bail!(
+                    "face {face} uses vertex {idx}, there are only {}",
+                    self.positions.len()
+                );
+            }
+
+            // triangulate the polygon as a fan around its first corner
+            for pair in polygon[1..].windows(2) {
+                self.indices
+                    .push([polygon[0] as u32, pair[0] as u32, pair[1] as u32]);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn build(self, mat: Arc<dyn Material + Sync + Send>) -> Result<TriangleMesh> {
+        if self.positions.is_empty() {
+            bail!("no vertices");
+        }
+        if self.indices.is_empty() {
+            bail!("no faces");
+        }
+
+        TriangleMesh::new(
+            self.positions,
+            self.normals,
+            self.uvs,
+            self.colors,
+            self.indices,
+            mat,
+        )
+    }
+}
@@ -1,122 +1,87 @@
-use std::sync::Arc;
-
-use crate::hittable::HittableList;
-use bvh::BVHNode;
-use camera::Camera;
-use color::Color;
-use material::{Dielectric, Lambertian, Material, Metal};
-use shapes::sphere::Sphere;
-use vector::{Point3, Vec3};
-
 mod aabb;
+mod background;
 mod bvh;
 mod camera;
 mod color;
+mod environment;
 mod hittable;
+mod light;
+mod light_sampler;
+mod loader;
 mod material;
+mod medium;
+mod microfacet;
+mod pdf;
+mod perlin;
 mod ray;
+mod sampling;
+mod scenes;
 mod shapes;
+mod sky;
+mod texture;
+mod transform;
 mod vector;
 
-use anyhow::Result;
-
-fn main() -> Result<()> {
-    let mut world = HittableList::new();
-
-    let ground_material = Lambertian::new(Color::new(0.5, 0.5, 0.5));
-    let sphere = Sphere::new(
-        Point3::new(0., -1000., 0.),
-        1000.,
-        Arc::new(ground_material),
-    );
-
-    world.add(Arc::new(sphere));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat: f32 = fastrand_contrib::f32_range(0.0..1.0);
-            let centre = Point3::new(
-                a as f32 + 0.9 * fastrand_contrib::f32_range(0.0..1.0),
-                0.2,
-                b as f32 + 0.9 * fastrand_contrib::f32_range(0.0..1.0),
-            );
-
-            if (centre - Vec3::new(4.0, 0.2, 0.)).len() > 0.9 {
-                let sphere_meterial: Arc<dyn Material + Send + Sync>;
+use crate::{light_sampler::LightSampling, pdf::MisHeuristic};
 
-                if choose_mat < 0.8 {
-                    // diffuse
-                    let albedo = Color::random_range(0.0, 1.0) * Color::random_range(0.0, 1.0);
-                    sphere_meterial = Arc::new(Lambertian::new(albedo));
-                    let sphere = Sphere::new(centre, 0.2, sphere_meterial);
-                    world.add(Arc::new(sphere));
-                } else if choose_mat < 0.95 {
-                    // metal
+use anyhow::{Result, anyhow, bail};
 
-                    let albedo = Color::random_range(0.5, 1.0);
-                    let fuzz = fastrand_contrib::f32_range(0.0..0.5);
-                    sphere_meterial = Arc::new(Metal::new(albedo, fuzz));
-                    let sphere = Sphere::new(centre, 0.2, sphere_meterial);
-                    world.add(Arc::new(sphere));
-                } else {
-                    sphere_meterial = Arc::new(Dielectric::new(1.5));
-                    let sphere = Sphere::new(centre, 0.2, sphere_meterial);
-                    world.add(Arc::new(sphere));
-                }
-            }
+fn main() -> Result<()> {
+    // pick the scene to render from the first command line argument,
+    // scenes that load a file take its path as the second, and
+    // `--name=value` options anywhere change how the camera renders
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (options, args): (Vec<&str>, Vec<&str>) = args
+        .iter()
+        .map(String::as_str)
+        .partition(|arg| arg.starts_with("--"));
+    let scene = args.first().copied().unwrap_or("spheres");
+    let file = || {
+        args.get(1)
+            .copied()
+            .ok_or_else(|| anyhow!("scene `{scene}` needs a file to load"))
+    };
+
+    let (world, mut cam) = match scene {
+        "spheres" => scenes::bouncing_spheres(false)?,
+        "motion" => scenes::bouncing_spheres(true)?,
+        "checkered" => scenes::checkered_spheres()?,
+        "earth" => scenes::earth()?,
+        "perlin" => scenes::perlin_spheres()?,
+        "light" => scenes::simple_light()?,
+        "environment" => scenes::environment()?,
+        "metals" => scenes::metals()?,
+        "frosted" => scenes::frosted_glass()?,
+        "tinted" => scenes::tinted_glass()?,
+        "principled" => scenes::principled()?,
+        "delta_lights" => scenes::delta_lights()?,
+        "sky" => scenes::sky()?,
+        "city_night" => scenes::city_night()?,
+        "ies" => scenes::ies_lights(file()?)?,
+        "quads" => scenes::quads()?,
+        "cornell" => scenes::cornell_box()?,
+        "cornell_smoke" => scenes::cornell_smoke()?,
+        "cloud" => scenes::cloud()?,
+        "volume" => scenes::volume_grid(file()?)?,
+        "meshes" => scenes::meshes()?,
+        "mesh_lights" => scenes::mesh_lights()?,
+        "instances" => scenes::instances()?,
+        "obj" => scenes::obj_model(file()?)?,
+        "ply" => scenes::ply_model(file()?)?,
+        _ => bail!("unknown scene `{scene}`"),
+    };
+
+    for option in options {
+        match option.split_once('=') {
+            Some(("--mis", "power")) => cam.heuristic = MisHeuristic::Power,
+            Some(("--mis", "balance")) => cam.heuristic = MisHeuristic::Balance,
+            Some(("--lights", "uniform")) => cam.light_sampling = LightSampling::Uniform,
+            Some(("--lights", "power")) => cam.light_sampling = LightSampling::Power,
+            Some(("--lights", "tree")) => cam.light_sampling = LightSampling::Tree,
+            _ => bail!("unknown option `{option}`"),
         }
     }
 
-    let material1 = Arc::new(Dielectric::new(1.5));
-    let material2 = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
-    let material3 = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
-
-    world.add(Arc::new(Sphere::new(
-        Point3::new(0., 1., 0.),
-        1.,
-        material1,
-    )));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(-4., 1., 0.),
-        1.,
-        material2,
-    )));
-    world.add(Arc::new(Sphere::new(
-        Point3::new(4.0, 1., 0.),
-        1.,
-        material3,
-    )));
-
-    let bvh_node = BVHNode::from_hittable_list(world);
-    let mut world = HittableList::new();
-    world.add(Arc::new(bvh_node));
-
-    let aspect_ratio = 16.0 / 9.0;
-    let image_width = 1200;
-    let samples_per_pixel = 500;
-    let max_bounce_depth = 50;
-
-    let vfov = 20.0;
-    let lookfrom = Point3::new(13., 2., 3.);
-    let lookat = Point3::new(0., 0., 0.);
-    let vup = Vec3::new(0., 1., 0.);
-
-    let defocus_angle = 0.6;
-    let focus_dist = 10.0;
-
-    let cam = Camera::new(
-        aspect_ratio,
-        image_width,
-        samples_per_pixel,
-        max_bounce_depth,
-        vfov,
-        lookfrom,
-        lookat,
-        vup,
-        defocus_angle,
-        focus_dist,
-    );
-
     cam.render(&world)?;
 
     Ok(())
@@ -1,48 +1,113 @@
-use crate::{color::Color, hittable::HitRecord, ray::Ray, vector::Vec3};
+use std::{f32, sync::Arc};
+
+use crate::{
+    color::Color,
+    hittable::HitRecord,
+    microfacet::{TrowbridgeReitz, fresnel_conductor, fresnel_dielectric, reflect, refract},
+    pdf::{CosinePdf, Pdf, SpherePdf},
+    ray::Ray,
+    texture::{SolidColor, Texture},
+    vector::{Onb, Vec3},
+};
 
 pub trait Material {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Scatter;
+
+    // BSDF times the cosine term for light arriving along `dir` and leaving back along r_in,
+    // zero for materials that only scatter specularly
+    #[inline(always)]
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _dir: &Vec3) -> Color {
+        Color::zero()
+    }
+
+    // solid angle density of `scatter` sampling `dir`
+    #[inline(always)]
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _dir: &Vec3) -> f32 {
+        0.
+    }
+
+    // radiance given off by the surface at the hit point, black for everything but lights
+    #[inline(always)]
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
+        Color::zero()
+    }
+
+    // absorption coefficient of the inside of a closed surface, light travelling through it
+    // is attenuated by exp(-sigma * distance)
+    #[inline(always)]
+    fn absorption(&self) -> Option<Color> {
+        None
+    }
+
+    // surfaces made of emissive materials are collected as area lights
+    #[inline(always)]
+    fn is_emissive(&self) -> bool {
+        false
+    }
 }
 
 pub enum Scatter {
-    Scattered(Ray, Color), // scattered ray and attenuation
+    // ray sampled from the material's pdf, with `eval` and `pdf` for its direction
+    Sampled { r: Ray, f: Color, pdf: f32 },
+    // perfectly specular bounce that cannot be evaluated for any other direction
+    Specular(Ray, Color), // scattered ray and attenuation
     Absorbed,
 }
 
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture + Sync + Send>,
 }
 
 impl Lambertian {
     #[inline(always)]
-    pub const fn new(albedo: Color) -> Self {
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    #[inline(always)]
+    pub fn from_texture(albedo: Arc<dyn Texture + Sync + Send>) -> Self {
         Self { albedo }
     }
 }
 
 impl Material for Lambertian {
     #[inline(always)]
-    fn scatter(&self, _r_in: &Ray, rec: &HitRecord) -> Scatter {
-        let mut scatter_dir = *rec.normal() + Vec3::random_unit_vec();
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Scatter {
+        let dir = CosinePdf::new(rec.normal()).generate();
+        let r = Ray::new(rec.p, dir, r_in.time);
 
-        if scatter_dir.near_zero() {
-            scatter_dir = *rec.normal();
+        Scatter::Sampled {
+            f: self.eval(r_in, rec, &dir),
+            pdf: self.pdf(r_in, rec, &dir),
+            r,
         }
+    }
 
-        let r = Ray::new(rec.p, scatter_dir);
+    #[inline(always)]
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Color {
+        let cosine = dir.unit_vec().dot(rec.normal()).max(0.);
+        (cosine / f32::consts::PI) * self.albedo.value(rec)
+    }
 
-        Scatter::Scattered(r, self.albedo)
+    #[inline(always)]
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> f32 {
+        CosinePdf::new(rec.normal()).value(dir)
     }
 }
 
 pub struct Metal {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture + Sync + Send>,
     pub fuzz: f32,
 }
 
 impl Metal {
     #[inline(always)]
-    pub const fn new(albedo: Color, fuzz: f32) -> Self {
+    pub fn new(albedo: Color, fuzz: f32) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    #[inline(always)]
+    pub fn from_texture(albedo: Arc<dyn Texture + Sync + Send>, fuzz: f32) -> Self {
         Self { albedo, fuzz }
     }
 }
@@ -53,26 +118,192 @@ impl Material for Metal {
         // fuzz factor adds randomness to the scattering
         let reflected =
             r_in.dir.reflect(rec.normal()).unit_vec() + (self.fuzz * Vec3::random_unit_vec());
-        let r = Ray::new(rec.p, reflected);
+        let r = Ray::new(rec.p, reflected, r_in.time);
 
         if r.dir.dot(rec.normal()) > 0. {
-            Scatter::Scattered(r, self.albedo)
+            Scatter::Specular(r, self.albedo.value(rec))
         } else {
             Scatter::Absorbed
         }
     }
 }
 
+// rough metal, a GGX microfacet conductor with the Fresnel reflectance of its
+// complex index of refraction eta + ik, given per color channel
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    pub distribution: TrowbridgeReitz,
+}
+
+#[allow(unused)]
+impl Conductor {
+    #[inline(always)]
+    pub fn new(eta: Color, k: Color, roughness: f32) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::from_roughness(roughness),
+        }
+    }
+
+    #[inline(always)]
+    pub fn gold(roughness: f32) -> Self {
+        Self::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    #[inline(always)]
+    pub fn copper(roughness: f32) -> Self {
+        Self::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    #[inline(always)]
+    pub fn aluminium(roughness: f32) -> Self {
+        Self::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    #[inline(always)]
+    pub fn silver(roughness: f32) -> Self {
+        Self::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    // outgoing and incident directions in the shading frame
+    #[inline(always)]
+    fn local_dirs(r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> (Vec3, Vec3) {
+        let uvw = Onb::new(rec.normal());
+        (
+            uvw.local(&-r_in.dir.unit_vec()),
+            uvw.local(&dir.unit_vec()),
+        )
+    }
+}
+
+impl Material for Conductor {
+    #[inline(always)]
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Scatter {
+        let uvw = Onb::new(rec.normal());
+        let wo = uvw.local(&-r_in.dir.unit_vec());
+        if wo.z <= 0. {
+            return Scatter::Absorbed;
+        }
+
+        if self.distribution.effectively_smooth() {
+            let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+            let r = Ray::new(rec.p, uvw.transform(&wi), r_in.time);
+            return Scatter::Specular(r, fresnel_conductor(wo.z, &self.eta, &self.k));
+        }
+
+        // reflect about a sampled visible microfacet, some reflections end up below
+        // the surface and the path is lost, as multiple scattering is not modelled
+        let wm = self.distribution.sample_wm(&wo);
+        let wi = reflect(&wo, &wm);
+        if wi.z <= 0. {
+            return Scatter::Absorbed;
+        }
+
+        let dir = uvw.transform(&wi);
+        Scatter::Sampled {
+            f: self.eval(r_in, rec, &dir),
+            pdf: self.pdf(r_in, rec, &dir),
+            r: Ray::new(rec.p, dir, r_in.time),
+        }
+    }
+
+    #[inline(always)]
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Color {
+        if self.distribution.effectively_smooth() {
+            return Color::zero();
+        }
+
+        let (wo, wi) = Self::local_dirs(r_in, rec, dir);
+        if wo.z <= 0. || wi.z <= 0. {
+            return Color::zero();
+        }
+
+        let wm = wo + wi;
+        if wm.near_zero() {
+            return Color::zero();
+        }
+        let wm = wm.unit_vec();
+
+        // D G F / (4 cos_o cos_i), times cos_i
+        let f = fresnel_conductor(wo.dot(&wm).abs(), &self.eta, &self.k);
+        let dg = self.distribution.d(&wm) * self.distribution.g(&wo, &wi);
+        (dg / (4. * wo.z)) * f
+    }
+
+    #[inline(always)]
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> f32 {
+        if self.distribution.effectively_smooth() {
+            return 0.;
+        }
+
+        let (wo, wi) = Self::local_dirs(r_in, rec, dir);
+        if wo.z <= 0. || wi.z <= 0. {
+            return 0.;
+        }
+
+        let wm = wo + wi;
+        if wm.near_zero() {
+            return 0.;
+        }
+        let wm = wm.unit_vec();
+
+        // visible normal density, changed to the density of the reflected direction
+        self.distribution.d_visible(&wo, &wm) / (4. * wo.dot(&wm).abs())
+    }
+}
+
 // refractive index in vacuum or air
 // or the ratio of the refractive index over the refractive index of the enclosing media
 pub struct Dielectric {
     pub refract_idx: f32,
+    pub absorption: Option<Color>, // per unit distance inside, None for clear glass
 }
 
+#[allow(unused)]
 impl Dielectric {
     #[inline(always)]
     pub fn new(refract_idx: f32) -> Self {
-        Self { refract_idx }
+        Self {
+            refract_idx,
+            absorption: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn absorbing(refract_idx: f32, absorption: Color) -> Self {
+        Self {
+            refract_idx,
+            absorption: Some(absorption),
+        }
+    }
+
+    // glass that lets through `color` of the light after travelling `distance` inside it,
+    // going darker and more saturated where it is thicker
+    #[inline(always)]
+    pub fn tinted(refract_idx: f32, color: Color, distance: f32) -> Self {
+        let sigma = |c: f32| -c.clamp(1e-6, 1.).ln() / distance;
+        Self::absorbing(
+            refract_idx,
+            Color::new(sigma(color.x), sigma(color.y), sigma(color.z)),
+        )
     }
 
     #[inline(always)]
@@ -111,7 +342,606 @@ impl Material for Dielectric {
             r_in_unit_dir.refract(rec.normal(), ri)
         };
 
-        let r = Ray::new(rec.p, dir);
-        Scatter::Scattered(r, atten)
+        let r = Ray::new(rec.p, dir, r_in.time);
+        Scatter::Specular(r, atten)
+    }
+
+    #[inline(always)]
+    fn absorption(&self) -> Option<Color> {
+        self.absorption
+    }
+}
+
+// frosted or etched glass, a GGX microfacet interface that both reflects and transmits
+pub struct RoughDielectric {
+    pub refract_idx: f32,
+    pub distribution: TrowbridgeReitz,
+}
+
+#[allow(unused)]
+impl RoughDielectric {
+    #[inline(always)]
+    pub fn new(refract_idx: f32, roughness: f32) -> Self {
+        Self {
+            refract_idx,
+            distribution: TrowbridgeReitz::from_roughness(roughness),
+        }
+    }
+
+    // shading frame, outgoing direction in it and the index of refraction of the far side
+    // relative to the side the ray arrives from, following the front/back face convention
+    #[inline(always)]
+    fn local_frame(&self, r_in: &Ray, rec: &HitRecord) -> (Onb, Vec3, f32) {
+        let uvw = Onb::new(rec.normal());
+        let wo = uvw.local(&-r_in.dir.unit_vec());
+        let eta = if rec.face_normal.is_front() {
+            self.refract_idx
+        } else {
+            1.0 / self.refract_idx
+        };
+
+        (uvw, wo, eta)
+    }
+
+    // microfacet normal that takes `wo` to `wi`, with the relative index of refraction
+    // along the way, 1 for reflection
+    #[inline(always)]
+    fn half_vector(wo: &Vec3, wi: &Vec3, eta: f32) -> Option<(Vec3, f32)> {
+        if wo.z <= 0. || wi.z == 0. {
+            return None;
+        }
+
+        let etap = if wi.z > 0. { 1. } else { eta };
+        let wm = etap * *wi + *wo;
+        if wm.near_zero() {
+            return None;
+        }
+
+        let mut wm = wm.unit_vec();
+        if wm.z < 0. {
+            wm = -wm;
+        }
+
+        // microfacets facing away from either direction do not contribute
+        if wm.dot(wi) * wi.z < 0. || wm.dot(wo) * wo.z < 0. {
+            return None;
+        }
+
+        Some((wm, etap))
+    }
+}
+
+impl Material for RoughDielectric {
+    #[inline(always)]
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Scatter {
+        let (uvw, wo, eta) = self.local_frame(r_in, rec);
+        if wo.z <= 0. {
+            return Scatter::Absorbed;
+        }
+
+        if self.distribution.effectively_smooth() {
+            let n = Vec3::new(0., 0., 1.);
+            let reflectance = fresnel_dielectric(wo.z, eta);
+
+            let (wi, atten) = match refract(&wo, &n, eta) {
+                Some(wt) if fastrand::f32() >= reflectance => (wt, Color::one()),
+                _ => (reflect(&wo, &n), Color::one()),
+            };
+
+            let r = Ray::new(rec.p, uvw.transform(&wi), r_in.time);
+            return Scatter::Specular(r, atten);
+        }
+
+        // pick reflection or transmission through a sampled visible microfacet
+        // in proportion to its Fresnel reflectance
+        let wm = self.distribution.sample_wm(&wo);
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+
+        let wi = if fastrand::f32() < reflectance {
+            reflect(&wo, &wm)
+        } else {
+            match refract(&wo, &wm, eta) {
+                Some(wt) => wt,
+                None => return Scatter::Absorbed,
+            }
+        };
+
+        // directions that end up on the wrong side of the macro surface are lost
+        let reflected = wo.dot(&wm) * wi.dot(&wm) > 0.;
+        if reflected != (wi.z > 0.) {
+            return Scatter::Absorbed;
+        }
+
+        let dir = uvw.transform(&wi);
+        Scatter::Sampled {
+            f: self.eval(r_in, rec, &dir),
+            pdf: self.pdf(r_in, rec, &dir),
+            r: Ray::new(rec.p, dir, r_in.time),
+        }
+    }
+
+    #[inline(always)]
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Color {
+        if self.distribution.effectively_smooth() {
+            return Color::zero();
+        }
+
+        let (uvw, wo, eta) = self.local_frame(r_in, rec);
+        let wi = uvw.local(&dir.unit_vec());
+        let Some((wm, etap)) = Self::half_vector(&wo, &wi, eta) else {
+            return Color::zero();
+        };
+
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+        let dg = self.distribution.d(&wm) * self.distribution.g(&wo, &wi);
+
+        let f = if wi.z > 0. {
+            dg * reflectance / (4. * wo.z)
+        } else {
+            // like Dielectric, transmitted radiance is not scaled by 1/eta^2
+            let denom = (wi.dot(&wm) + wo.dot(&wm) / etap).powi(2);
+            let jacobian = (wi.dot(&wm) * wo.dot(&wm)).abs() / (wo.z * denom);
+            dg * (1. - reflectance) * jacobian
+        };
+
+        f * Color::one()
+    }
+
+    #[inline(always)]
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> f32 {
+        if self.distribution.effectively_smooth() {
+            return 0.;
+        }
+
+        let (uvw, wo, eta) = self.local_frame(r_in, rec);
+        let wi = uvw.local(&dir.unit_vec());
+        let Some((wm, etap)) = Self::half_vector(&wo, &wi, eta) else {
+            return 0.;
+        };
+
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+        let d_visible = self.distribution.d_visible(&wo, &wm);
+
+        if wi.z > 0. {
+            d_visible / (4. * wo.dot(&wm).abs()) * reflectance
+        } else {
+            let denom = (wi.dot(&wm) + wo.dot(&wm) / etap).powi(2);
+            d_visible * wi.dot(&wm).abs() / denom * (1. - reflectance)
+        }
+    }
+}
+
+// scalar material parameter, either fixed or read from the first channel of a texture
+#[derive(Clone)]
+pub enum Param {
+    Constant(f32),
+    Texture(Arc<dyn Texture + Sync + Send>),
+}
+
+impl Param {
+    #[inline(always)]
+    pub fn value(&self, rec: &HitRecord) -> f32 {
+        match self {
+            Param::Constant(v) => *v,
+            Param::Texture(texture) => texture.value(rec).x,
+        }
+    }
+}
+
+impl From<f32> for Param {
+    #[inline(always)]
+    fn from(v: f32) -> Self {
+        Param::Constant(v)
+    }
+}
+
+impl From<Arc<dyn Texture + Sync + Send>> for Param {
+    #[inline(always)]
+    fn from(texture: Arc<dyn Texture + Sync + Send>) -> Self {
+        Param::Texture(texture)
+    }
+}
+
+// Disney style uber material mixing a diffuse base with sheen, GGX specular reflection,
+// rough transmission and a clearcoat layer, all parameters are in [0, 1] apart from ior
+pub struct Principled {
+    pub base_color: Arc<dyn Texture + Sync + Send>,
+    pub metallic: Param,
+    pub roughness: Param, // clamped to at least 0.05, use the specular materials for mirrors
+    pub specular: Param,  // dielectric reflectance, 0.5 is a 4% reflection like most plastics
+    pub specular_tint: Param, // tints dielectric reflections towards the base color
+    pub sheen: Param,     // extra grazing reflection for cloth
+    pub clearcoat: Param, // strength of a glossy, uncolored top layer
+    pub transmission: Param, // how much of the non-metallic part is glass rather than diffuse
+    pub ior: Param,       // index of refraction of the transmissive part
+}
+
+#[allow(unused)]
+impl Principled {
+    // a plain dielectric with the default parameters
+    #[inline(always)]
+    pub fn new(base_color: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(base_color)))
+    }
+
+    #[inline(always)]
+    pub fn from_texture(base_color: Arc<dyn Texture + Sync + Send>) -> Self {
+        Self {
+            base_color,
+            metallic: 0.0.into(),
+            roughness: 0.5.into(),
+            specular: 0.5.into(),
+            specular_tint: 0.0.into(),
+            sheen: 0.0.into(),
+            clearcoat: 0.0.into(),
+            transmission: 0.0.into(),
+            ior: 1.5.into(),
+        }
+    }
+
+    #[inline(always)]
+    fn lobes(&self, r_in: &Ray, rec: &HitRecord) -> PrincipledLobes {
+        let base = self.base_color.value(rec);
+        let metallic = self.metallic.value(rec).clamp(0., 1.);
+        let roughness = self.roughness.value(rec).clamp(0.05, 1.);
+        let specular = self.specular.value(rec).max(0.);
+        let specular_tint = self.specular_tint.value(rec).clamp(0., 1.);
+        let transmission = self.transmission.value(rec).clamp(0., 1.);
+        let ior = self.ior.value(rec).max(1.0001);
+
+        // hue and saturation of the base color with its brightness taken out
+        let lum = base.luminance();
+        let tint = if lum > 0. { base / lum } else { Color::one() };
+
+        let dielectric_f0 = specular * 0.08 * lerp(&Color::one(), &tint, specular_tint);
+
+        let uvw = Onb::new(rec.normal());
+        let wo = uvw.local(&-r_in.dir.unit_vec());
+
+        let lobes = PrincipledLobes {
+            uvw,
+            wo,
+            base,
+            roughness,
+            diffuse: (1. - metallic) * (1. - transmission),
+            sheen: self.sheen.value(rec).max(0.) * lerp(&Color::one(), &tint, 0.5),
+            specular_f0: lerp(&dielectric_f0, &base, metallic),
+            transmission: (1. - metallic) * transmission,
+            clearcoat: 0.25 * self.clearcoat.value(rec).max(0.),
+            eta: if rec.face_normal.is_front() {
+                ior
+            } else {
+                1. / ior
+            },
+            distribution: TrowbridgeReitz::from_roughness(roughness),
+            clearcoat_distribution: TrowbridgeReitz::from_roughness(0.1),
+            weights: [0.; 4],
+        };
+
+        lobes.with_weights()
+    }
+}
+
+impl Material for Principled {
+    #[inline(always)]
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Scatter {
+        let lobes = self.lobes(r_in, rec);
+        let Some(wi) = lobes.sample() else {
+            return Scatter::Absorbed;
+        };
+
+        let pdf = lobes.pdf(&wi);
+        if pdf <= 0. {
+            return Scatter::Absorbed;
+        }
+
+        Scatter::Sampled {
+            f: lobes.eval(&wi),
+            pdf,
+            r: Ray::new(rec.p, lobes.uvw.transform(&wi), r_in.time),
+        }
+    }
+
+    #[inline(always)]
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> Color {
+        let lobes = self.lobes(r_in, rec);
+        lobes.eval(&lobes.uvw.local(&dir.unit_vec()))
+    }
+
+    #[inline(always)]
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, dir: &Vec3) -> f32 {
+        let lobes = self.lobes(r_in, rec);
+        lobes.pdf(&lobes.uvw.local(&dir.unit_vec()))
+    }
+}
+
+const DIFFUSE: usize = 0;
+const SPECULAR: usize = 1;
+const TRANSMISSION: usize = 2;
+const CLEARCOAT: usize = 3;
+
+// a Principled material evaluated at one hit, in the shading frame where wo.z > 0
+struct PrincipledLobes {
+    uvw: Onb,
+    wo: Vec3,
+    base: Color,
+    roughness: f32,
+    diffuse: f32,
+    sheen: Color,
+    specular_f0: Color,
+    transmission: f32,
+    clearcoat: f32,
+    eta: f32, // index of refraction of the far side relative to the near side
+    distribution: TrowbridgeReitz,
+    clearcoat_distribution: TrowbridgeReitz,
+    weights: [f32; 4], // probability of sampling each lobe
+}
+
+impl PrincipledLobes {
+    // lobes are picked roughly in proportion to how much light they reflect towards wo
+    #[inline(always)]
+    fn with_weights(mut self) -> Self {
+        let cos_o = self.wo.z.clamp(0., 1.);
+        let lum = self.base.luminance().max(0.01);
+
+        self.weights = [
+            self.diffuse * lum,
+            schlick(&self.specular_f0, cos_o).luminance(),
+            self.transmission * lum,
+            self.clearcoat * schlick(&Color::new(0.04, 0.04, 0.04), cos_o).x,
+        ];
+
+        let sum: f32 = self.weights.iter().sum();
+        if sum > 0. {
+            for w in self.weights.iter_mut() {
+                *w /= sum;
+            }
+        }
+
+        self
+    }
+
+    #[inline(always)]
+    fn sample(&self) -> Option<Vec3> {
+        let wo = &self.wo;
+        if wo.z <= 0. {
+            return None;
+        }
+
+        // pick a lobe
+        let mut u = fastrand::f32();
+        let mut lobe = CLEARCOAT;
+        for (i, w) in self.weights.iter().enumerate() {
+            if u < *w {
+                lobe = i;
+                break;
+            }
+            u -= w;
+        }
+
+        let wi = match lobe {
+            DIFFUSE => Vec3::random_cosine_direction(),
+            SPECULAR => reflect(wo, &self.distribution.sample_wm(wo)),
+            TRANSMISSION => refract(wo, &self.distribution.sample_wm(wo), self.eta)?,
+            _ => reflect(wo, &self.clearcoat_distribution.sample_wm(wo)),
+        };
+
+        // reflections below the surface and transmissions above it are lost
+        if (lobe == TRANSMISSION) != (wi.z < 0.) {
+            return None;
+        }
+
+        Some(wi)
+    }
+
+    // the sum of every lobe, times the cosine term
+    #[inline(always)]
+    fn eval(&self, wi: &Vec3) -> Color {
+        let wo = &self.wo;
+        if wo.z <= 0. || wi.z == 0. {
+            return Color::zero();
+        }
+
+        if wi.z < 0. {
+            return self.eval_transmission(wi);
+        }
+
+        let wm = (*wo + *wi).unit_vec();
+        let cos_d = wi.dot(&wm).clamp(0., 1.);
+        let (cos_i, cos_o) = (wi.z, wo.z);
+
+        // Burley diffuse, with retro-reflection at grazing angles on rough surfaces,
+        // and the sheen
+        let fd90 = 0.5 + 2. * self.roughness * cos_d * cos_d;
+        let fd =
+            (1. + (fd90 - 1.) * (1. - cos_i).powi(5)) * (1. + (fd90 - 1.) * (1. - cos_o).powi(5));
+        let sheen = (1. - cos_d).powi(5) * self.sheen;
+        let diffuse = self.diffuse * ((fd / f32::consts::PI) * self.base + sheen);
+
+        // D G F / (4 cos_o cos_i) for both microfacet layers
+        let specular = (self.distribution.d(&wm) * self.distribution.g(wo, wi)
+            / (4. * cos_o * cos_i))
+            * schlick(&self.specular_f0, cos_d);
+
+        let clearcoat_f = schlick(&Color::new(0.04, 0.04, 0.04), cos_d);
+        let clearcoat = (self.clearcoat
+            * self.clearcoat_distribution.d(&wm)
+            * self.clearcoat_distribution.g(wo, wi)
+            / (4. * cos_o * cos_i))
+            * clearcoat_f;
+
+        (diffuse + specular + clearcoat) * cos_i
+    }
+
+    #[inline(always)]
+    fn eval_transmission(&self, wi: &Vec3) -> Color {
+        let wo = &self.wo;
+        let Some(wm) = self.transmission_half_vector(wi) else {
+            return Color::zero();
+        };
+
+        let reflectance = fresnel_dielectric(wo.dot(&wm), self.eta);
+        let dg = self.distribution.d(&wm) * self.distribution.g(wo, wi);
+        let denom = (wi.dot(&wm) + wo.dot(&wm) / self.eta).powi(2);
+        let jacobian = (wi.dot(&wm) * wo.dot(&wm)).abs() / (wo.z * denom);
+
+        (self.transmission * dg * (1. - reflectance) * jacobian / (self.eta * self.eta)) * self.base
+    }
+
+    #[inline(always)]
+    fn transmission_half_vector(&self, wi: &Vec3) -> Option<Vec3> {
+        let wo = &self.wo;
+        let wm = self.eta * *wi + *wo;
+        if wm.near_zero() {
+            return None;
+        }
+
+        let mut wm = wm.unit_vec();
+        if wm.z < 0. {
+            wm = -wm;
+        }
+
+        if wm.dot(wi) * wi.z < 0. || wm.dot(wo) * wo.z < 0. {
+            return None;
+        }
+
+        Some(wm)
+    }
+
+    // each lobe's density weighted by the chance of picking it
+    #[inline(always)]
+    fn pdf(&self, wi: &Vec3) -> f32 {
+        let wo = &self.wo;
+        if wo.z <= 0. || wi.z == 0. {
+            return 0.;
+        }
+
+        if wi.z < 0. {
+            let Some(wm) = self.transmission_half_vector(wi) else {
+                return 0.;
+            };
+
+            let denom = (wi.dot(&wm) + wo.dot(&wm) / self.eta).powi(2);
+            return self.weights[TRANSMISSION]
+                * self.distribution.d_visible(wo, &wm)
+                * wi.dot(&wm).abs()
+                / denom;
+        }
+
+        let wm = (*wo + *wi).unit_vec();
+        let to_reflected = 4. * wo.dot(&wm).abs();
+
+        self.weights[DIFFUSE] * wi.z / f32::consts::PI
+            + self.weights[SPECULAR] * self.distribution.d_visible(wo, &wm) / to_reflected
+            + self.weights[CLEARCOAT] * self.clearcoat_distribution.d_visible(wo, &wm)
+                / to_reflected
+    }
+}
+
+// Schlick's approximation of the Fresnel reflectance for normal incidence reflectance f0
+#[inline(always)]
+fn schlick(f0: &Color, cosine: f32) -> Color {
+    *f0 + (1. - cosine.clamp(0., 1.)).powi(5) * (Color::one() - *f0)
+}
+
+#[inline(always)]
+fn lerp(a: &Color, b: &Color, t: f32) -> Color {
+    (1. - t) * *a + t * *b
+}
+
+// emits light from its surface and does not reflect anything
+pub struct DiffuseLight {
+    pub emit: Arc<dyn Texture + Sync + Send>,
+}
+
+impl DiffuseLight {
+    #[inline(always)]
+    pub fn new(emit: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(emit)))
+    }
+
+    #[inline(always)]
+    pub fn from_texture(emit: Arc<dyn Texture + Sync + Send>) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    #[inline(always)]
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord) -> Scatter {
+        Scatter::Absorbed
+    }
+
+    #[inline(always)]
+    fn emitted(&self, _r_in: &Ray, rec: &HitRecord) -> Color {
+        self.emit.value(rec)
+    }
+
+    #[inline(always)]
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
+
+// scatters uniformly in all directions, the phase function of a participating medium
+// a glowing medium can also give off radiance at every scattering event
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture + Sync + Send>,
+    pub emit: Option<Arc<dyn Texture + Sync + Send>>,
+}
+
+impl Isotropic {
+    #[inline(always)]
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    #[inline(always)]
+    pub fn from_texture(albedo: Arc<dyn Texture + Sync + Send>) -> Self {
+        Self { albedo, emit: None }
+    }
+
+    #[inline(always)]
+    pub fn emissive(
+        albedo: Arc<dyn Texture + Sync + Send>,
+        emit: Arc<dyn Texture + Sync + Send>,
+    ) -> Self {
+        Self {
+            albedo,
+            emit: Some(emit),
+        }
+    }
+}
+
+impl Material for Isotropic {
+    #[inline(always)]
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Scatter {
+        let dir = SpherePdf.generate();
+        let r = Ray::new(rec.p, dir, r_in.time);
+
+        Scatter::Sampled {
+            f: self.eval(r_in, rec, &dir),
+            pdf: self.pdf(r_in, rec, &dir),
+            r,
+        }
+    }
+
+    // the phase function has no cosine term
+    #[inline(always)]
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, _dir: &Vec3) -> Color {
+        self.albedo.value(rec) / (4. * f32::consts::PI)
+    }
+
+    #[inline(always)]
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, dir: &Vec3) -> f32 {
+        SpherePdf.value(dir)
+    }
+
+    #[inline(always)]
+    fn emitted(&self, _r_in: &Ray, rec: &HitRecord) -> Color {
+        match &self.emit {
+            Some(emit) => emit.value(rec),
+            None => Color::zero(),
+        }
     }
 }
@@ -0,0 +1,380 @@
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    hittable::{HitRecord, Hittable},
+    material::{Isotropic, Material},
+    ray::{FaceNormal, Interval, Ray},
+    texture::Texture,
+    vector::{Point3, Vec3},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+// participating medium of uniform density filling a closed boundary, like smoke or fog
+pub struct ConstantMedium {
+    pub boundary: Arc<dyn Hittable + Sync + Send>,
+    neg_inv_density: f32,
+    pub phase_function: Arc<dyn Material + Sync + Send>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Arc<dyn Hittable + Sync + Send>, density: f32, albedo: Color) -> Self {
+        Self::from_material(boundary, density, Arc::new(Isotropic::new(albedo)))
+    }
+
+    pub fn from_material(
+        boundary: Arc<dyn Hittable + Sync + Send>,
+        density: f32,
+        phase_function: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1. / density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    #[inline(always)]
+    fn hit(&self, r: &Ray, intvl: &Interval) -> Option<HitRecord> {
+        // find where the whole line enters and leaves the boundary, so rays that
+        // start inside the medium are handled too
+        let rec1 = self.boundary.hit(r, &Interval::UNIVERSE)?;
+        let rec2 = self
+            .boundary
+            .hit(r, &Interval::new(rec1.t + 0.0001, f32::INFINITY))?;
+
+        let inside = Interval::new(rec1.t.max(intvl.min).max(0.), rec2.t.min(intvl.max));
+
+        // sample a free-flight distance and see if it ends inside the boundary
+        let hit_distance = self.neg_inv_density * (1. - fastrand::f32()).ln();
+        let t = inside.min + hit_distance / r.dir.len();
+        if !inside.surrounds(t) {
+            return None;
+        }
+
+        // the normal and side are meaningless inside a volume
+        Some(HitRecord::new(
+            t,
+            r.at(t),
+            FaceNormal::Front(Vec3::new(1., 0., 0.)),
+            0.,
+            0.,
+            self.phase_function.clone(),
+        ))
+    }
+
+    #[inline(always)]
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+// one cell of a DensityGrid
+#[derive(Clone, Copy)]
+pub struct Voxel {
+    pub density: f32,
+    pub albedo: Color,
+    pub emission: Color,
+}
+
+impl Voxel {
+    #[inline(always)]
+    pub const fn new(density: f32, albedo: Color, emission: Color) -> Self {
+        Self {
+            density,
+            albedo,
+            emission,
+        }
+    }
+}
+
+// dense 3D grid of voxels, x varies fastest, then y, then z
+pub struct DensityGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    pub voxels: Vec<Voxel>,
+}
+
+#[allow(unused)]
+impl DensityGrid {
+    pub fn new(nx: usize, ny: usize, nz: usize, voxels: Vec<Voxel>) -> Result<Self> {
+        let count = Self::voxel_count(nx, ny, nz)?;
+        if voxels.len() != count {
+            bail!(
+                "a {nx}x{ny}x{nz} grid needs {count} voxels, found {}",
+                voxels.len()
+            );
+        }
+
+        Ok(Self { nx, ny, nz, voxels })
+    }
+
+    // number of voxels in a grid of this size, which has to have some and fit in memory
+    fn voxel_count(nx: usize, ny: usize, nz: usize) -> Result<usize> {
+        if nx == 0 || ny == 0 || nz == 0 {
+            bail!("a {nx}x{ny}x{nz} grid has no voxels");
+        }
+
+        nx.checked_mul(ny)
+            .and_then(|n| n.checked_mul(nz))
+            .ok_or_else(|| anyhow!("a {nx}x{ny}x{nz} grid has too many voxels"))
+    }
+
+    // fills the grid by evaluating `f` at the centre of every voxel, in [0, 1]^3 grid space
+    pub fn from_fn(nx: usize, ny: usize, nz: usize, f: impl Fn(&Point3) -> Voxel) -> Result<Self> {
+        let mut voxels = Vec::with_capacity(Self::voxel_count(nx, ny, nz)?);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let p = Point3::new(
+                        (x as f32 + 0.5) / nx as f32,
+                        (y as f32 + 0.5) / ny as f32,
+                        (z as f32 + 0.5) / nz as f32,
+                    );
+                    voxels.push(f(&p));
+                }
+            }
+        }
+
+        Self::new(nx, ny, nz, voxels)
+    }
+
+    // Raw little endian grid: four u32 for nx, ny, nz and the channel count, then
+    // the voxels as f32. With 1 channel each voxel is just a density (albedo is white),
+    // 4 adds an rgb albedo and 7 adds an rgb emission after that.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data =
+            std::fs::read(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+        let mut words = data.chunks_exact(4).map(|w| [w[0], w[1], w[2], w[3]]);
+        let mut header = [0usize; 4];
+        for h in header.iter_mut() {
+            let word = words
+                .next()
+                .ok_or_else(|| anyhow!("{}: truncated header", path.display()))?;
+            *h = u32::from_le_bytes(word) as usize;
+        }
+
+        let [nx, ny, nz, channels] = header;
+        if !matches!(channels, 1 | 4 | 7) {
+            bail!("{}: unsupported channel count {channels}", path.display());
+        }
+
+        let expected = Self::voxel_count(nx, ny, nz)
+            .with_context(|| path.display().to_string())?
+            .checked_mul(channels * 4)
+            .and_then(|n| n.checked_add(16))
+            .ok_or_else(|| anyhow!("{}: a {nx}x{ny}x{nz} grid is too large", path.display()))?;
+        if data.len() != expected {
+            bail!(
+                "{}: expected {expected} bytes for a {nx}x{ny}x{nz} grid with {channels} channels, found {}",
+                path.display(),
+                data.len()
+            );
+        }
+
+        let values: Vec<f32> = words.map(f32::from_le_bytes).collect();
+        let voxels = values
+            .chunks_exact(channels)
+            .map(|v| {
+                let albedo = if channels >= 4 {
+                    Color::new(v[1], v[2], v[3])
+                } else {
+                    Color::one()
+                };
+                let emission = if channels >= 7 {
+                    Color::new(v[4], v[5], v[6])
+                } else {
+                    Color::zero()
+                };
+                Voxel::new(v[0].max(0.), albedo, emission)
+            })
+            .collect();
+
+        Self::new(nx, ny, nz, voxels).with_context(|| path.display().to_string())
+    }
+
+    #[inline(always)]
+    fn voxel(&self, x: usize, y: usize, z: usize) -> &Voxel {
+        &self.voxels[(z * self.ny + y) * self.nx + x]
+    }
+
+    pub fn max_density(&self) -> f32 {
+        self.voxels.iter().map(|v| v.density).fold(0., f32::max)
+    }
+
+    // trilinearly interpolated density at a point in [0, 1]^3 grid space
+    #[inline(always)]
+    pub fn density(&self, p: &Point3) -> f32 {
+        // voxel values sit at the cell centres
+        let gx = p.x * self.nx as f32 - 0.5;
+        let gy = p.y * self.ny as f32 - 0.5;
+        let gz = p.z * self.nz as f32 - 0.5;
+
+        let (x0, fx) = Self::split(gx, self.nx);
+        let (y0, fy) = Self::split(gy, self.ny);
+        let (z0, fz) = Self::split(gz, self.nz);
+        let x1 = (x0 + 1).min(self.nx - 1);
+        let y1 = (y0 + 1).min(self.ny - 1);
+        let z1 = (z0 + 1).min(self.nz - 1);
+
+        let lerp = |a: f32, b: f32, t: f32| a + t * (b - a);
+        let d = |x, y, z| self.voxel(x, y, z).density;
+
+        let c00 = lerp(d(x0, y0, z0), d(x1, y0, z0), fx);
+        let c10 = lerp(d(x0, y1, z0), d(x1, y1, z0), fx);
+        let c01 = lerp(d(x0, y0, z1), d(x1, y0, z1), fx);
+        let c11 = lerp(d(x0, y1, z1), d(x1, y1, z1), fx);
+
+        lerp(lerp(c00, c10, fy), lerp(c01, c11, fy), fz)
+    }
+
+    // nearest voxel to a point in [0, 1]^3 grid space
+    #[inline(always)]
+    pub fn nearest(&self, p: &Point3) -> &Voxel {
+        let x = ((p.x * self.nx as f32).max(0.) as usize).min(self.nx - 1);
+        let y = ((p.y * self.ny as f32).max(0.) as usize).min(self.ny - 1);
+        let z = ((p.z * self.nz as f32).max(0.) as usize).min(self.nz - 1);
+
+        self.voxel(x, y, z)
+    }
+
+    // lower cell index and fractional offset, clamped to the grid
+    #[inline(always)]
+    fn split(g: f32, n: usize) -> (usize, f32) {
+        let g = g.clamp(0., (n - 1) as f32);
+        let i = (g.floor() as usize).min(n - 1);
+        (i, g - i as f32)
+    }
+}
+
+// albedo or emission of the voxel a hit falls in, used as the medium's phase function textures
+struct GridTexture {
+    grid: Arc<DensityGrid>,
+    min: Point3,
+    extent: Vec3,
+    emission: bool,
+}
+
+impl Texture for GridTexture {
+    #[inline(always)]
+    fn value(&self, rec: &HitRecord) -> Color {
+        let local = rec.p - self.min;
+        let p = Point3::new(
+            local.x / self.extent.x,
+            local.y / self.extent.y,
+            local.z / self.extent.z,
+        );
+
+        let voxel = self.grid.nearest(&p);
+        if self.emission {
+            voxel.emission
+        } else {
+            voxel.albedo
+        }
+    }
+}
+
+// heterogeneous medium whose density comes from a DensityGrid stretched over a box,
+// free-flight distances are sampled with delta tracking against the grid's maximum density
+pub struct GridMedium {
+    pub grid: Arc<DensityGrid>,
+    pub density_scale: f32,
+    majorant: f32,
+    min: Point3,
+    extent: Vec3,
+    bbox: Aabb,
+    phase_function: Arc<dyn Material + Sync + Send>,
+}
+
+impl GridMedium {
+    // the grid spans the box with corners `a` and `b`, its densities multiplied by `density_scale`
+    // fails if the box is flat along any axis
+    pub fn new(grid: Arc<DensityGrid>, a: &Point3, b: &Point3, density_scale: f32) -> Result<Self> {
+        let bbox = Aabb::new(a, b);
+        let min = Point3::new(bbox.x.min, bbox.y.min, bbox.z.min);
+        let extent = Vec3::new(bbox.x.size(), bbox.y.size(), bbox.z.size());
+        if extent.x <= 0. || extent.y <= 0. || extent.z <= 0. {
+            bail!("a grid medium needs a box with volume");
+        }
+
+        let texture = |emission| {
+            Arc::new(GridTexture {
+                grid: grid.clone(),
+                min,
+                extent,
+                emission,
+            })
+        };
+        let phase_function = Arc::new(Isotropic::emissive(texture(false), texture(true)));
+
+        Ok(Self {
+            majorant: grid.max_density() * density_scale,
+            grid,
+            density_scale,
+            min,
+            extent,
+            bbox,
+            phase_function,
+        })
+    }
+
+    #[inline(always)]
+    fn density_at(&self, p: &Point3) -> f32 {
+        let local = *p - self.min;
+        let p = Point3::new(
+            local.x / self.extent.x,
+            local.y / self.extent.y,
+            local.z / self.extent.z,
+        );
+
+        self.grid.density(&p) * self.density_scale
+    }
+}
+
+impl Hittable for GridMedium {
+    #[inline(always)]
+    fn hit(&self, r: &Ray, intvl: &Interval) -> Option<HitRecord> {
+        if self.majorant <= 0. {
+            return None;
+        }
+
+        let inside = self
+            .bbox
+            .clip(r, &Interval::new(intvl.min.max(0.), intvl.max))?;
+
+        // delta tracking: step through the box with the majorant as a homogeneous density,
+        // and accept each tentative collision with probability density / majorant
+        let ray_length = r.dir.len();
+        let mut t = inside.min;
+        loop {
+            t -= (1. - fastrand::f32()).ln() / (self.majorant * ray_length);
+            if !inside.surrounds(t) {
+                return None;
+            }
+
+            let p = r.at(t);
+            if fastrand::f32() * self.majorant < self.density_at(&p) {
+                return Some(HitRecord::new(
+                    t,
+                    p,
+                    FaceNormal::Front(Vec3::new(1., 0., 0.)),
+                    0.,
+                    0.,
+                    self.phase_function.clone(),
+                ));
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+}
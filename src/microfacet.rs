@@ -0,0 +1,192 @@
+use std::f32;
+
+use crate::{color::Color, vector::Vec3};
+
+// Trowbridge-Reitz (GGX) distribution of microfacet normals, in a local frame with the
+// surface normal along +z
+#[derive(Clone, Copy)]
+pub struct TrowbridgeReitz {
+    pub alpha_x: f32,
+    pub alpha_y: f32,
+}
+
+#[allow(unused)]
+impl TrowbridgeReitz {
+    #[inline(always)]
+    pub fn new(alpha_x: f32, alpha_y: f32) -> Self {
+        Self { alpha_x, alpha_y }
+    }
+
+    // isotropic distribution with alpha = roughness^2, which looks perceptually linear
+    #[inline(always)]
+    pub fn from_roughness(roughness: f32) -> Self {
+        let alpha = roughness.clamp(0., 1.).powi(2);
+        Self::new(alpha, alpha)
+    }
+
+    // too smooth to sample, treat as a perfect mirror instead
+    #[inline(always)]
+    pub fn effectively_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    // density of microfacets facing `wm`
+    #[inline(always)]
+    pub fn d(&self, wm: &Vec3) -> f32 {
+        let cos2_theta = wm.z * wm.z;
+        if cos2_theta <= 0. {
+            return 0.;
+        }
+
+        let e = (wm.x / self.alpha_x).powi(2) + (wm.y / self.alpha_y).powi(2);
+        let tan2_e = e / cos2_theta;
+
+        1. / (f32::consts::PI
+            * self.alpha_x
+            * self.alpha_y
+            * cos2_theta
+            * cos2_theta
+            * (1. + tan2_e).powi(2))
+    }
+
+    // Smith's auxiliary function, the masked microfacet area per visible area
+    #[inline(always)]
+    pub fn lambda(&self, w: &Vec3) -> f32 {
+        let cos2_theta = w.z * w.z;
+        if cos2_theta <= 0. {
+            return 0.;
+        }
+
+        let alpha2_tan2 =
+            ((w.x * self.alpha_x).powi(2) + (w.y * self.alpha_y).powi(2)) / cos2_theta;
+        ((1. + alpha2_tan2).sqrt() - 1.) / 2.
+    }
+
+    // fraction of microfacets visible from `w`
+    #[inline(always)]
+    pub fn g1(&self, w: &Vec3) -> f32 {
+        1. / (1. + self.lambda(w))
+    }
+
+    // fraction visible from both directions (height correlated masking-shadowing)
+    #[inline(always)]
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f32 {
+        1. / (1. + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // density of the normals visible from `w`
+    #[inline(always)]
+    pub fn d_visible(&self, w: &Vec3, wm: &Vec3) -> f32 {
+        if w.z == 0. {
+            return 0.;
+        }
+
+        self.g1(w) / w.z.abs() * self.d(wm) * w.dot(wm).abs()
+    }
+
+    // samples a microfacet normal visible from `w` (Heitz 2018)
+    #[inline(always)]
+    pub fn sample_wm(&self, w: &Vec3) -> Vec3 {
+        // stretch to the hemisphere configuration
+        let mut wh = Vec3::new(self.alpha_x * w.x, self.alpha_y * w.y, w.z).unit_vec();
+        if wh.z < 0. {
+            wh = -wh;
+        }
+
+        let t1 = if wh.z < 0.99999 {
+            Vec3::new(0., 0., 1.).cross(&wh).unit_vec()
+        } else {
+            Vec3::new(1., 0., 0.)
+        };
+        let t2 = wh.cross(&t1);
+
+        // uniform point on the disk, squashed onto the visible half
+        let r = fastrand::f32().sqrt();
+        let phi = 2. * f32::consts::PI * fastrand::f32();
+        let (px, mut py) = (r * phi.cos(), r * phi.sin());
+
+        let h = (1. - px * px).max(0.).sqrt();
+        let s = (1. + wh.z) / 2.;
+        py = (1. - s) * h + s * py;
+
+        let pz = (1. - px * px - py * py).max(0.).sqrt();
+        let nh = px * t1 + py * t2 + pz * wh;
+
+        // back to the ellipsoid configuration
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).unit_vec()
+    }
+}
+
+// `w` mirrored about `n`, both pointing away from the surface
+#[inline(always)]
+pub fn reflect(w: &Vec3, n: &Vec3) -> Vec3 {
+    -*w + 2. * w.dot(n) * *n
+}
+
+// unpolarised reflectance of a conductor with complex index of refraction eta + ik,
+// per color channel
+#[inline(always)]
+pub fn fresnel_conductor(cos_theta_i: f32, eta: &Color, k: &Color) -> Color {
+    let channel = |eta: f32, k: f32| {
+        let cos = cos_theta_i.clamp(0., 1.);
+        let cos2 = cos * cos;
+        let sin2 = 1. - cos2;
+
+        let t0 = eta * eta - k * k - sin2;
+        let a2b2 = (t0 * t0 + 4. * eta * eta * k * k).sqrt();
+        let a = (0.5 * (a2b2 + t0)).max(0.).sqrt();
+
+        let t1 = a2b2 + cos2;
+        let t2 = 2. * a * cos;
+        let rs = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+
+        0.5 * (rp + rs)
+    };
+
+    Color::new(
+        channel(eta.x, k.x),
+        channel(eta.y, k.y),
+        channel(eta.z, k.z),
+    )
+}
+
+// unpolarised reflectance of a dielectric interface, `eta` is the index of refraction
+// on the far side relative to the side `cos_theta_i` is measured on
+#[inline(always)]
+pub fn fresnel_dielectric(cos_theta_i: f32, eta: f32) -> f32 {
+    let (mut cos_i, mut eta) = (cos_theta_i.clamp(-1., 1.), eta);
+    if cos_i < 0. {
+        // coming from the other side
+        eta = 1. / eta;
+        cos_i = -cos_i;
+    }
+
+    let sin2_t = (1. - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1. {
+        // total internal reflection
+        return 1.;
+    }
+    let cos_t = (1. - sin2_t).max(0.).sqrt();
+
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_parl * r_parl + r_perp * r_perp)
+}
+
+// `w` refracted through the interface with normal `n` on its side, pointing away from
+// the surface on the far side, None on total internal reflection
+#[inline(always)]
+pub fn refract(w: &Vec3, n: &Vec3, eta: f32) -> Option<Vec3> {
+    let cos_i = n.dot(w);
+    let sin2_t = (1. - cos_i * cos_i).max(0.) / (eta * eta);
+    if sin2_t >= 1. {
+        return None;
+    }
+
+    let cos_t = (1. - sin2_t).sqrt();
+    Some(-*w / eta + (cos_i / eta - cos_t) * *n)
+}
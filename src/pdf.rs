@@ -0,0 +1,144 @@
+use std::f32;
+
+use crate::{
+    hittable::Hittable,
+    ray::Ray,
+    vector::{Onb, Point3, Vec3},
+};
+
+// a distribution of directions that can be sampled and evaluated, densities are over solid angle
+pub trait Pdf {
+    fn value(&self, dir: &Vec3) -> f32;
+    fn generate(&self) -> Vec3;
+}
+
+// proportional to the cosine with the normal, over the hemisphere around it
+pub struct CosinePdf {
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    #[inline(always)]
+    pub fn new(normal: &Vec3) -> Self {
+        Self {
+            uvw: Onb::new(normal),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    #[inline(always)]
+    fn value(&self, dir: &Vec3) -> f32 {
+        let cosine = dir.unit_vec().dot(&self.uvw.w);
+        (cosine / f32::consts::PI).max(0.)
+    }
+
+    #[inline(always)]
+    fn generate(&self) -> Vec3 {
+        self.uvw.transform(&Vec3::random_cosine_direction())
+    }
+}
+
+// uniform over all directions
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    #[inline(always)]
+    fn value(&self, _dir: &Vec3) -> f32 {
+        1. / (4. * f32::consts::PI)
+    }
+
+    #[inline(always)]
+    fn generate(&self) -> Vec3 {
+        Vec3::random_unit_vec()
+    }
+}
+
+// directions from `origin` towards an object, through its `random` and `pdf_value`
+pub struct HittablePdf<'a> {
+    objects: &'a dyn Hittable,
+    origin: Point3,
+    time: f32,
+}
+
+impl<'a> HittablePdf<'a> {
+    #[inline(always)]
+    pub fn new(objects: &'a dyn Hittable, origin: &Point3, time: f32) -> Self {
+        Self {
+            objects,
+            origin: *origin,
+            time,
+        }
+    }
+}
+
+impl Pdf for HittablePdf<'_> {
+    #[inline(always)]
+    fn value(&self, dir: &Vec3) -> f32 {
+        self.objects
+            .pdf_value(&Ray::new(self.origin, *dir, self.time))
+    }
+
+    #[inline(always)]
+    fn generate(&self) -> Vec3 {
+        self.objects.random(&self.origin, self.time)
+    }
+}
+
+// picks from the first pdf with probability `weight` and from the second otherwise
+pub struct MixturePdf<'a> {
+    pdfs: [&'a dyn Pdf; 2],
+    weight: f32,
+}
+
+#[allow(unused)]
+impl<'a> MixturePdf<'a> {
+    #[inline(always)]
+    pub fn new(p0: &'a dyn Pdf, p1: &'a dyn Pdf, weight: f32) -> Self {
+        Self {
+            pdfs: [p0, p1],
+            weight: weight.clamp(0., 1.),
+        }
+    }
+
+    #[inline(always)]
+    pub fn even(p0: &'a dyn Pdf, p1: &'a dyn Pdf) -> Self {
+        Self::new(p0, p1, 0.5)
+    }
+}
+
+impl Pdf for MixturePdf<'_> {
+    #[inline(always)]
+    fn value(&self, dir: &Vec3) -> f32 {
+        self.weight * self.pdfs[0].value(dir) + (1. - self.weight) * self.pdfs[1].value(dir)
+    }
+
+    #[inline(always)]
+    fn generate(&self) -> Vec3 {
+        if fastrand::f32() < self.weight {
+            self.pdfs[0].generate()
+        } else {
+            self.pdfs[1].generate()
+        }
+    }
+}
+
+// weights for combining light and BSDF samples of the same direction (multiple importance sampling)
+#[derive(Clone, Copy)]
+pub enum MisHeuristic {
+    Balance,
+    Power,
+}
+
+impl MisHeuristic {
+    // weight of a sample drawn with density `pdf` when `other` could also have produced it
+    #[inline(always)]
+    pub fn weight(&self, pdf: f32, other: f32) -> f32 {
+        let (f, g) = match self {
+            MisHeuristic::Balance => (pdf, other),
+            MisHeuristic::Power => (pdf * pdf, other * other),
+        };
+
+        if f + g > 0. { f / (f + g) } else { 0. }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::vector::{Point3, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+// Perlin gradient noise, the gradients and permutations come from a seeded generator
+// so the same seed always gives the same noise
+pub struct Perlin {
+    ranvec: [Vec3; POINT_COUNT],
+    perm_x: [usize; POINT_COUNT],
+    perm_y: [usize; POINT_COUNT],
+    perm_z: [usize; POINT_COUNT],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = fastrand::Rng::with_seed(seed);
+
+        let mut ranvec = [Vec3::zero(); POINT_COUNT];
+        for v in ranvec.iter_mut() {
+            // random unit gradient
+            *v = loop {
+                let p = Vec3::new(
+                    2. * rng.f32() - 1.,
+                    2. * rng.f32() - 1.,
+                    2. * rng.f32() - 1.,
+                );
+                let lensq = p.len_squared();
+                if 1e-12 < lensq && lensq <= 1. {
+                    break p / lensq.sqrt();
+                }
+            };
+        }
+
+        let mut perm = || {
+            let mut p: [usize; POINT_COUNT] = std::array::from_fn(|i| i);
+            rng.shuffle(&mut p);
+            p
+        };
+        let perm_x = perm();
+        let perm_y = perm();
+        let perm_z = perm();
+
+        Self {
+            ranvec,
+            perm_x,
+            perm_y,
+            perm_z,
+        }
+    }
+
+    // noise in [-1, 1], trilinear interpolation of the lattice gradients with Hermite smoothing
+    pub fn noise(&self, p: &Point3) -> f32 {
+        let u = p.x - p.x.floor();
+        let v = p.y - p.y.floor();
+        let w = p.z - p.z.floor();
+
+        let i = p.x.floor() as i32;
+        let j = p.y.floor() as i32;
+        let k = p.z.floor() as i32;
+
+        let mut c = [[[Vec3::zero(); 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, grad) in row.iter_mut().enumerate() {
+                    let idx = self.perm_x[((i + di as i32) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i32) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i32) & 255) as usize];
+                    *grad = self.ranvec[idx];
+                }
+            }
+        }
+
+        Self::perlin_interp(&c, u, v, w)
+    }
+
+    // sum of `depth` octaves of noise, each at double the frequency and half the weight
+    pub fn turb(&self, p: &Point3, depth: u32) -> f32 {
+        let mut accum = 0.;
+        let mut temp_p = *p;
+        let mut weight = 1.;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p *= 2.;
+        }
+
+        accum.abs()
+    }
+
+    #[inline(always)]
+    fn perlin_interp(c: &[[[Vec3; 2]; 2]; 2], u: f32, v: f32, w: f32) -> f32 {
+        let uu = u * u * (3. - 2. * u);
+        let vv = v * v * (3. - 2. * v);
+        let ww = w * w * (3. - 2. * w);
+
+        let mut accum = 0.;
+        for (i, plane) in c.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, grad) in row.iter().enumerate() {
+                    let (fi, fj, fk) = (i as f32, j as f32, k as f32);
+                    let weight = Vec3::new(u - fi, v - fj, w - fk);
+
+                    accum += (fi * uu + (1. - fi) * (1. - uu))
+                        * (fj * vv + (1. - fj) * (1. - vv))
+                        * (fk * ww + (1. - fk) * (1. - ww))
+                        * grad.dot(&weight);
+                }
+            }
+        }
+
+        accum
+    }
+}
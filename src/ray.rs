@@ -3,12 +3,13 @@ use crate::vector::{Point3, Vec3};
 pub struct Ray {
     pub origin: Point3,
     pub dir: Vec3,
+    pub time: f32, // instant within the shutter interval the ray was shot at
 }
 
 impl Ray {
     #[inline]
-    pub fn new(origin: Point3, dir: Vec3) -> Self {
-        Ray { origin, dir }
+    pub fn new(origin: Point3, dir: Vec3, time: f32) -> Self {
+        Ray { origin, dir, time }
     }
 
     #[inline]
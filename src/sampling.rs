@@ -0,0 +1,280 @@
+use std::f32;
+
+use crate::vector::Vec3;
+
+// piecewise-constant 1D distribution over [0, 1), sampled by inverting its CDF
+pub struct Distribution1D {
+    pub func: Vec<f32>,
+    pub cdf: Vec<f32>,
+    pub integral: f32,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f32>) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.; n + 1];
+        for i in 1..=n {
+            cdf[i] = cdf[i - 1] + func[i - 1].abs() / n as f32;
+        }
+
+        let integral = cdf[n];
+        if integral == 0. {
+            // nothing to importance sample, fall back to uniform
+            for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+                *c = i as f32 / n as f32;
+            }
+        } else {
+            for c in cdf.iter_mut().skip(1) {
+                *c /= integral;
+            }
+        }
+
+        Self {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    #[inline(always)]
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    // returns the sampled position in [0, 1), its density and the index of the segment it fell in
+    #[inline(always)]
+    pub fn sample_continuous(&self, u: f32) -> (f32, f32, usize) {
+        // last cdf entry <= u
+        let offset = (self.cdf.partition_point(|&c| c <= u) - 1).min(self.count() - 1);
+
+        let mut du = u - self.cdf[offset];
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        if width > 0. {
+            du /= width;
+        }
+
+        let pdf = self.pdf(offset);
+        let x = (offset as f32 + du) / self.count() as f32;
+
+        (x.min(1. - f32::EPSILON), pdf, offset)
+    }
+
+    // density of the segment `offset` with respect to [0, 1)
+    #[inline(always)]
+    pub fn pdf(&self, offset: usize) -> f32 {
+        if self.integral > 0. {
+            self.func[offset].abs() / self.integral
+        } else {
+            1.
+        }
+    }
+}
+
+// piecewise-constant 2D distribution over [0, 1)^2, stored row by row
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f32], nu: usize, nv: usize) -> Self {
+        let conditional: Vec<_> = func
+            .chunks_exact(nu)
+            .take(nv)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral).collect());
+
+        Self {
+            conditional,
+            marginal,
+        }
+    }
+
+    // returns the sampled (u, v) and its density
+    #[inline(always)]
+    pub fn sample_continuous(&self, u0: f32, u1: f32) -> ((f32, f32), f32) {
+        let (v, pdf_v, row) = self.marginal.sample_continuous(u1);
+        let (u, pdf_u, _) = self.conditional[row].sample_continuous(u0);
+
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    #[inline(always)]
+    pub fn pdf(&self, u: f32, v: f32) -> f32 {
+        let nu = self.conditional[0].count();
+        let nv = self.marginal.count();
+        let iu = ((u * nu as f32) as usize).min(nu - 1);
+        let iv = ((v * nv as f32) as usize).min(nv - 1);
+
+        if self.marginal.integral > 0. {
+            self.conditional[iv].func[iu] / self.marginal.integral
+        } else {
+            1.
+        }
+    }
+}
+
+// Walker's alias method, picks index i with probability proportional to its weight in
+// constant time: one uniform pick of a bin, then a coin flip between it and its alias
+pub struct AliasTable {
+    prob: Vec<f32>,    // chance of keeping the bin rather than taking its alias
+    alias: Vec<usize>, // the other index sharing the bin
+    pmf: Vec<f32>,
+}
+
+impl AliasTable {
+    // all zero weights give a uniform table
+    pub fn new(weights: &[f32]) -> Self {
+        let n = weights.len();
+        let sum: f32 = weights.iter().map(|w| w.max(0.)).sum();
+        let pmf: Vec<f32> = if sum > 0. {
+            weights.iter().map(|w| w.max(0.) / sum).collect()
+        } else {
+            vec![1. / n as f32; n]
+        };
+
+        // Vose's construction: bins scaled to an average of 1, under-full ones are topped
+        // up from over-full ones until every bin holds exactly 1
+        let mut scaled: Vec<f32> = pmf.iter().map(|p| p * n as f32).collect();
+        let mut prob = vec![1.; n];
+        let mut alias: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| scaled[i] < 1.);
+
+        // whatever is left over once either list runs out is full up to rounding
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            prob[s] = scaled[s];
+            alias[s] = l;
+            scaled[l] -= 1. - scaled[s];
+            if scaled[l] < 1. {
+                large.pop();
+                small.push(l);
+            }
+        }
+
+        Self { prob, alias, pmf }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.pmf.len()
+    }
+
+    // returns the picked index and its probability
+    #[inline(always)]
+    pub fn sample(&self) -> Option<(usize, f32)> {
+        if self.pmf.is_empty() {
+            return None;
+        }
+
+        let bin = fastrand::usize(..self.len());
+        let i = if fastrand::f32() < self.prob[bin] {
+            bin
+        } else {
+            self.alias[bin]
+        };
+
+        Some((i, self.pmf[i]))
+    }
+
+    #[inline(always)]
+    pub fn pmf(&self, i: usize) -> f32 {
+        self.pmf[i]
+    }
+}
+
+// solid angle of the spherical triangle with unit vertices `a`, `b` and `c`, after
+// Van Oosterom and Strackee
+#[inline(always)]
+pub fn spherical_triangle_area(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
+    let num = a.dot(&b.cross(c)).abs();
+    let den = 1. + a.dot(b) + a.dot(c) + b.dot(c);
+
+    2. * num.atan2(den)
+}
+
+// spherical triangle with unit vertices `a`, `b` and `c`, set up for drawing uniformly
+// distributed directions inside it, from Arvo's "Stratified sampling of spherical triangles"
+pub struct SphericalTriangle {
+    a: Vec3,
+    b: Vec3,
+    c: Vec3,
+    alpha: f32,  // angle at `a`
+    excess: f32, // of the angles over pi, which is the area
+}
+
+impl SphericalTriangle {
+    // None for triangles too thin to sample, with an edge or the area vanishing
+    pub fn new(a: &Vec3, b: &Vec3, c: &Vec3) -> Option<Self> {
+        // normals of the planes through the origin and each edge
+        let n_ab = a.cross(b);
+        let n_bc = b.cross(c);
+        let n_ca = c.cross(a);
+        if n_ab.len_squared() == 0. || n_bc.len_squared() == 0. || n_ca.len_squared() == 0. {
+            return None;
+        }
+        let (n_ab, n_bc, n_ca) = (n_ab.unit_vec(), n_bc.unit_vec(), n_ca.unit_vec());
+
+        // angles at the vertices, their excess over pi is the area
+        let alpha = angle_between(&n_ab, &-n_ca);
+        let beta = angle_between(&n_bc, &-n_ab);
+        let gamma = angle_between(&n_ca, &-n_bc);
+        let excess = alpha + beta + gamma - f32::consts::PI;
+        if excess <= 0. {
+            return None;
+        }
+
+        Some(Self {
+            a: *a,
+            b: *b,
+            c: *c,
+            alpha,
+            excess,
+        })
+    }
+
+    pub fn sample(&self, u0: f32, u1: f32) -> Vec3 {
+        let Self { a, b, c, alpha, .. } = self;
+
+        // pick the area of the sub-triangle, then the vertex c' on the arc from a to c
+        // that cuts it off
+        let area_pi = f32::consts::PI + u0 * self.excess;
+        let (sin_alpha, cos_alpha) = alpha.sin_cos();
+        let (sin_a, cos_a) = area_pi.sin_cos();
+        let sin_phi = sin_a * cos_alpha - cos_a * sin_alpha;
+        let cos_phi = cos_a * cos_alpha + sin_a * sin_alpha;
+
+        let k1 = cos_phi + cos_alpha;
+        let k2 = sin_phi - sin_alpha * a.dot(b);
+        let cos_bp = ((k2 + (k2 * cos_phi - k1 * sin_phi) * cos_alpha)
+            / ((k2 * sin_phi + k1 * cos_phi) * sin_alpha))
+            .clamp(-1., 1.);
+        let sin_bp = (1. - cos_bp * cos_bp).max(0.).sqrt();
+        let cp = cos_bp * *a + sin_bp * (*c - c.dot(a) * *a).unit_vec();
+
+        // then a point on the arc from b to c'
+        let cos_theta = 1. - u1 * (1. - cp.dot(b));
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let w = cos_theta * *b + sin_theta * (cp - cp.dot(b) * *b).unit_vec();
+
+        // round-off can still break down for the odd sample, which then goes to the middle
+        // of the triangle rather than switching to another strategy
+        if w.x.is_finite() {
+            w
+        } else {
+            (*a + *b + *c).unit_vec()
+        }
+    }
+}
+
+// angle between two unit vectors, accurate for nearly equal and nearly opposite ones
+#[inline(always)]
+fn angle_between(v1: &Vec3, v2: &Vec3) -> f32 {
+    if v1.dot(v2) < 0. {
+        f32::consts::PI - 2. * ((*v1 + *v2).len() / 2.).min(1.).asin()
+    } else {
+        2. * ((*v2 - *v1).len() / 2.).min(1.).asin()
+    }
+}
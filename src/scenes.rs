@@ -0,0 +1,1391 @@
+use std::{f32, sync::Arc};
+
+use crate::{
+    background::{GradientBackground, SolidBackground},
+    bvh::BVHNode,
+    camera::Camera,
+    color::Color,
+    environment::EnvironmentMap,
+    hittable::{Hittable, HittableList},
+    light::{DirectionalLight, PointLight, SpotLight},
+    loader::{ies::load_ies, obj::load_obj, ply::load_ply},
+    material::{
+        Conductor, Dielectric, DiffuseLight, Lambertian, Material, Metal, Param, Principled,
+        RoughDielectric,
+    },
+    medium::{ConstantMedium, DensityGrid, GridMedium, Voxel},
+    shapes::{
+        quad::{Quad, make_box},
+        sphere::Sphere,
+        triangle::TriangleMesh,
+    },
+    sky::{PreethamSky, SunPosition},
+    texture::{
+        CheckerTexture, ImageTexture, MarbleTexture, NoiseTexture, Texture, TurbulenceTexture,
+        VertexColorTexture, WoodTexture,
+    },
+    transform::Transform,
+    vector::{Mat4, Point3, Vec3},
+};
+
+use anyhow::Result;
+
+// with `motion_blur` the small diffuse spheres bounce up while the shutter is open
+pub fn bouncing_spheres(motion_blur: bool) -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    let ground_material = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+    let sphere = Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(ground_material),
+    );
+
+    world.add(Arc::new(sphere));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat: f32 = fastrand_contrib::f32_range(0.0..1.0);
+            let centre = Point3::new(
+                a as f32 + 0.9 * fastrand_contrib::f32_range(0.0..1.0),
+                0.2,
+                b as f32 + 0.9 * fastrand_contrib::f32_range(0.0..1.0),
+            );
+
+            if (centre - Vec3::new(4.0, 0.2, 0.)).len() > 0.9 {
+                let sphere_meterial: Arc<dyn Material + Send + Sync>;
+
+                if choose_mat < 0.8 {
+                    // diffuse
+                    let albedo = Color::random_range(0.0, 1.0) * Color::random_range(0.0, 1.0);
+                    sphere_meterial = Arc::new(Lambertian::new(albedo));
+                    let centre1 = if motion_blur {
+                        centre + Vec3::new(0., fastrand_contrib::f32_range(0.0..0.5), 0.)
+                    } else {
+                        centre
+                    };
+                    let sphere = Sphere::new_moving(centre, centre1, 0.2, sphere_meterial);
+                    world.add(Arc::new(sphere));
+                } else if choose_mat < 0.95 {
+                    // metal
+
+                    let albedo = Color::random_range(0.5, 1.0);
+                    let fuzz = fastrand_contrib::f32_range(0.0..0.5);
+                    sphere_meterial = Arc::new(Metal::new(albedo, fuzz));
+                    let sphere = Sphere::new(centre, 0.2, sphere_meterial);
+                    world.add(Arc::new(sphere));
+                } else {
+                    sphere_meterial = Arc::new(Dielectric::new(1.5));
+                    let sphere = Sphere::new(centre, 0.2, sphere_meterial);
+                    world.add(Arc::new(sphere));
+                }
+            }
+        }
+    }
+
+    let material1 = Arc::new(Dielectric::new(1.5));
+    let material2 = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+    let material3 = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., 1., 0.),
+        1.,
+        material1,
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-4., 1., 0.),
+        1.,
+        material2,
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(4.0, 1., 0.),
+        1.,
+        material3,
+    )));
+
+    let bvh_node = BVHNode::from_hittable_list(world);
+    let mut world = HittableList::new();
+    world.add(Arc::new(bvh_node));
+
+    let aspect_ratio = 16.0 / 9.0;
+    let image_width = 1200;
+    let samples_per_pixel = 500;
+    let max_bounce_depth = 50;
+
+    let vfov = 20.0;
+    let lookfrom = Point3::new(13., 2., 3.);
+    let lookat = Point3::new(0., 0., 0.);
+    let vup = Vec3::new(0., 1., 0.);
+
+    let defocus_angle = 0.6;
+    let focus_dist = 10.0;
+
+    let cam = Camera::new(
+        aspect_ratio,
+        image_width,
+        samples_per_pixel,
+        max_bounce_depth,
+        vfov,
+        lookfrom,
+        lookat,
+        vup,
+        defocus_angle,
+        focus_dist,
+    );
+
+    Ok((world, cam))
+}
+
+pub fn checkered_spheres() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.32,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    let material = Arc::new(Lambertian::from_texture(checker));
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -10., 0.),
+        10.,
+        material.clone(),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., 10., 0.),
+        10.,
+        material,
+    )));
+
+    let cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        20.0,
+        Point3::new(13., 2., 3.),
+        Point3::new(0., 0., 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    Ok((world, cam))
+}
+
+// expects an equirectangular map of the earth at ./earthmap.png
+pub fn earth() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    let earth_texture = Arc::new(ImageTexture::open("earthmap.png")?);
+    let earth_surface = Arc::new(Lambertian::from_texture(earth_texture));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., 0., 0.),
+        2.,
+        earth_surface,
+    )));
+
+    let cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        20.0,
+        Point3::new(0., 0., 12.),
+        Point3::new(0., 0., 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    Ok((world, cam))
+}
+
+pub fn perlin_spheres() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    let marble = Arc::new(MarbleTexture::new(
+        1,
+        4.,
+        Color::new(0.9, 0.9, 0.85),
+        Color::new(0.2, 0.2, 0.25),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::from_texture(marble)),
+    )));
+
+    let textures: [Arc<dyn Texture + Sync + Send>; 3] = [
+        Arc::new(NoiseTexture::new(2, 4., Color::one())),
+        Arc::new(TurbulenceTexture::new(3, 4., 7, Color::new(0.8, 0.6, 0.3))),
+        Arc::new(WoodTexture::new(
+            4,
+            6.,
+            Color::new(0.75, 0.55, 0.3),
+            Color::new(0.4, 0.22, 0.1),
+        )),
+    ];
+
+    for (i, texture) in textures.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0., 2., 4.5 * (i as f32 - 1.)),
+            2.,
+            Arc::new(Lambertian::from_texture(texture)),
+        )));
+    }
+
+    let cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        30.0,
+        Point3::new(26., 3., 6.),
+        Point3::new(0., 2., 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    Ok((world, cam))
+}
+
+pub fn simple_light() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        2.0,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., 2., 0.),
+        2.,
+        Arc::new(Metal::new(Color::new(0.8, 0.6, 0.4), 0.1)),
+    )));
+
+    let light = Arc::new(DiffuseLight::new(Color::new(4., 4., 4.)));
+    world.add(Arc::new(Sphere::new(Point3::new(0., 7., 0.), 2., light)));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        20.0,
+        Point3::new(26., 3., 6.),
+        Point3::new(0., 2., 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(SolidBackground::new(Color::zero()));
+
+    Ok((world, cam))
+}
+
+// expects a lat-long Radiance HDR image at ./environment.hdr
+pub fn environment() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-2.2, 1., 0.),
+        1.,
+        Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.2))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., 1., 0.),
+        1.,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(2.2, 1., 0.),
+        1.,
+        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.05)),
+    )));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        30.0,
+        Point3::new(0., 2., 10.),
+        Point3::new(0., 1., 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(EnvironmentMap::open("environment.hdr", 0., 1.)?);
+
+    Ok((world, cam))
+}
+
+// the conductor presets, polished in the back row and rough in the front
+pub fn metals() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        1.0,
+        Color::new(0.2, 0.2, 0.2),
+        Color::new(0.8, 0.8, 0.8),
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    let presets: [fn(f32) -> Conductor; 4] = [
+        Conductor::gold,
+        Conductor::copper,
+        Conductor::aluminium,
+        Conductor::silver,
+    ];
+
+    for (i, preset) in presets.iter().enumerate() {
+        let x = 2. * (i as f32 - 1.5);
+        for (z, roughness) in [(-1., 0.1), (1., 0.5)] {
+            world.add(Arc::new(Sphere::new(
+                Point3::new(x, 0.8, z),
+                0.8,
+                Arc::new(preset(roughness)),
+            )));
+        }
+    }
+
+    let light = Arc::new(DiffuseLight::new(Color::new(8., 8., 8.)));
+    world.add(Arc::new(Sphere::new(Point3::new(-3., 8., 6.), 1.5, light)));
+
+    let cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        30.0,
+        Point3::new(0., 4., 12.),
+        Point3::new(0., 0.6, 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    Ok((world, cam))
+}
+
+// glass going from clear to heavily frosted, in front of a checkered wall
+pub fn frosted_glass() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.8, 0.1, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-6., 0., -3.),
+        Vec3::new(12., 0., 0.),
+        Vec3::new(0., 6., 0.),
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    for (i, roughness) in [0., 0.1, 0.25, 0.5].into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(2.2 * (i as f32 - 1.5), 1., 0.),
+            1.,
+            Arc::new(RoughDielectric::new(1.5, roughness)),
+        )));
+    }
+
+    let light = Arc::new(DiffuseLight::new(Color::new(6., 6., 6.)));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-2., 7., 1.),
+        Vec3::new(4., 0., 0.),
+        Vec3::new(0., 0., 3.),
+        light,
+    )));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        35.0,
+        Point3::new(0., 2.5, 10.),
+        Point3::new(0., 1.2, 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(SolidBackground::new(Color::new(0.1, 0.1, 0.12)));
+
+    Ok((world, cam))
+}
+
+// the same tinted glass in growing thicknesses, the color deepens with the distance
+// light travels inside
+pub fn tinted_glass() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+    )));
+
+    let green: Arc<dyn Material + Sync + Send> =
+        Arc::new(Dielectric::tinted(1.5, Color::new(0.4, 0.8, 0.5), 1.));
+    for (x, radius) in [(-3.2, 0.4), (-1.7, 0.8), (0.6, 1.2)] {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(x, radius, 0.),
+            radius,
+            green.clone(),
+        )));
+    }
+
+    let ruby = Arc::new(Dielectric::tinted(1.77, Color::new(0.8, 0.1, 0.15), 0.5));
+    let gem = Transform::new(
+        Arc::new(make_box(
+            &Point3::new(-0.6, 0., -0.6),
+            &Point3::new(0.6, 1.2, 0.6),
+            ruby,
+        )),
+        Mat4::translation(&Vec3::new(3.2, 0., 0.3)) * Mat4::rotation_y(30.),
+    )?;
+    world.add(Arc::new(gem));
+
+    let light = Arc::new(DiffuseLight::new(Color::new(8., 8., 8.)));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-2., 6., -2.),
+        Vec3::new(4., 0., 0.),
+        Vec3::new(0., 0., 4.),
+        light,
+    )));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        30.0,
+        Point3::new(0., 3., 12.),
+        Point3::new(0., 0.8, 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(SolidBackground::new(Color::new(0.2, 0.2, 0.25)));
+
+    Ok((world, cam))
+}
+
+// lit only by a point light, a spot light and a low sun with soft shadows
+pub fn delta_lights() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7))),
+    )));
+
+    let colors = [
+        Color::new(0.8, 0.2, 0.2),
+        Color::new(0.2, 0.8, 0.2),
+        Color::new(0.2, 0.2, 0.8),
+    ];
+    for (i, color) in colors.into_iter().enumerate() {
+        let mut mat = Principled::new(color);
+        mat.roughness = 0.4.into();
+        world.add(Arc::new(Sphere::new(
+            Point3::new(3. * (i as f32 - 1.), 1., 0.),
+            1.,
+            Arc::new(mat),
+        )));
+    }
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        35.0,
+        Point3::new(0., 4., 12.),
+        Point3::new(0., 0.8, 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(SolidBackground::new(Color::new(0.01, 0.01, 0.02)));
+
+    cam.lights.push(Arc::new(PointLight::new(
+        Point3::new(-3., 3., 3.),
+        Color::new(10., 8., 6.),
+    )));
+    cam.lights.push(Arc::new(SpotLight::new(
+        Point3::new(3., 6., 2.),
+        Point3::new(3., 0., 0.),
+        Color::new(40., 40., 50.),
+        10.,
+        20.,
+    )));
+    cam.lights.push(Arc::new(DirectionalLight::new(
+        Vec3::new(-1., 0.4, -0.5),
+        Color::new(0.6, 0.45, 0.3),
+        4.,
+    )));
+
+    Ok((world, cam))
+}
+
+// a few blocks of buildings under a clear afternoon sky, with the sun placed for Rome
+// in early summer
+pub fn sky() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    let ground_albedo = Color::new(0.3, 0.3, 0.28);
+    world.add(Arc::new(Quad::new(
+        Point3::new(-100., 0., -100.),
+        Vec3::new(200., 0., 0.),
+        Vec3::new(0., 0., 200.),
+        Arc::new(Lambertian::new(ground_albedo)),
+    )));
+
+    let walls: Arc<dyn Material + Sync + Send> =
+        Arc::new(Lambertian::new(Color::new(0.75, 0.7, 0.6)));
+    let blocks = [
+        (Point3::new(-6., 0., -8.), Point3::new(-2., 9., -4.)),
+        (Point3::new(-1., 0., -10.), Point3::new(3., 5., -6.)),
+        (Point3::new(4., 0., -7.), Point3::new(7., 12., -3.)),
+        (Point3::new(-4., 0., -1.), Point3::new(-1., 3., 2.)),
+    ];
+    for (a, b) in blocks {
+        world.add(Arc::new(make_box(&a, &b, walls.clone())));
+    }
+
+    let glass = Dielectric::tinted(1.5, Color::new(0.6, 0.8, 0.9), 1.);
+    world.add(Arc::new(Sphere::new(
+        Point3::new(2., 1., 1.),
+        1.,
+        Arc::new(glass),
+    )));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        50.0,
+        Point3::new(4., 3., 12.),
+        Point3::new(0., 3., -4.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    let sun = SunPosition::at(41.9, 12.5, 2024, 6, 15, 14.5);
+    let sky = PreethamSky::new(sun, 3., ground_albedo, 1.);
+    cam.lights.push(Arc::new(sky.sun_light()));
+    cam.background = Arc::new(sky);
+
+    Ok((world, cam))
+}
+
+// a street at night, lit by a few hundred windows and a row of street lamps
+pub fn city_night() -> Result<(HittableList, Camera)> {
+    let mut rng = fastrand::Rng::with_seed(7);
+    let mut objects = HittableList::new();
+
+    objects.add(Arc::new(Quad::new(
+        Point3::new(-60., 0., 20.),
+        Vec3::new(120., 0., 0.),
+        Vec3::new(0., 0., -180.),
+        Arc::new(Lambertian::new(Color::new(0.15, 0.15, 0.15))),
+    )));
+
+    let walls: Arc<dyn Material + Sync + Send> =
+        Arc::new(Lambertian::new(Color::new(0.45, 0.4, 0.35)));
+
+    // blocks on both sides of the street, windows on the facades facing it
+    for side in [-1., 1.] {
+        let mut z = 10.;
+        while z > -150. {
+            let depth = 5. + 4. * rng.f32();
+            let height = 6. + 24. * rng.f32();
+            let front = 5. * side;
+            let back = 15. * side;
+
+            objects.add(Arc::new(make_box(
+                &Point3::new(front, 0., z),
+                &Point3::new(back, height, z - depth),
+                walls.clone(),
+            )));
+
+            let x = front - 0.01 * side;
+            let mut wz = z - 0.6;
+            while wz - 0.8 > z - depth {
+                let mut wy = 1.;
+                while wy + 1.2 < height {
+                    if rng.f32() < 0.4 {
+                        let warmth = rng.f32();
+                        let emit = (1. + 5. * rng.f32())
+                            * Color::new(1., 0.7 + 0.2 * warmth, 0.35 + 0.4 * warmth);
+                        objects.add(Arc::new(Quad::new(
+                            Point3::new(x, wy, wz),
+                            Vec3::new(0., 0., -0.8),
+                            Vec3::new(0., 1.2, 0.),
+                            Arc::new(DiffuseLight::new(emit)),
+                        )));
+                    }
+                    wy += 2.5;
+                }
+                wz -= 1.6;
+            }
+
+            z -= depth + 1. + 2. * rng.f32();
+        }
+    }
+
+    // street lamps along both kerbs
+    let lamp: Arc<dyn Material + Sync + Send> =
+        Arc::new(DiffuseLight::new(Color::new(60., 45., 25.)));
+    for i in 0..16 {
+        for side in [-1., 1.] {
+            objects.add(Arc::new(Sphere::new(
+                Point3::new(3.8 * side, 5., 5. - 10. * i as f32 - 5. * (side + 1.) / 2.),
+                0.2,
+                lamp.clone(),
+            )));
+        }
+    }
+
+    let mut world = HittableList::new();
+    world.add(Arc::new(BVHNode::from_hittable_list(objects)));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        60.0,
+        Point3::new(1.5, 1.8, 14.),
+        Point3::new(-0.5, 4., -20.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(SolidBackground::new(Color::new(0.002, 0.003, 0.008)));
+
+    Ok((world, cam))
+}
+
+// a row of downlights washing a wall, plus a spot on a sphere, all with the candela
+// distribution of one IES file; lengths are in metres
+pub fn ies_lights(path: &str) -> Result<(HittableList, Camera)> {
+    let profile = Arc::new(load_ies(path)?);
+    let mut world = HittableList::new();
+
+    let white: Arc<dyn Material + Sync + Send> =
+        Arc::new(Lambertian::new(Color::new(0.75, 0.75, 0.75)));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-6., 0., -6.),
+        Vec3::new(12., 0., 0.),
+        Vec3::new(0., 0., 12.),
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-6., 0., -2.),
+        Vec3::new(12., 0., 0.),
+        Vec3::new(0., 3.5, 0.),
+        white,
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(1.5, 0.5, 0.5),
+        0.5,
+        Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.2))),
+    )));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        50.0,
+        Point3::new(0., 1.6, 6.),
+        Point3::new(0., 1.4, -2.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(SolidBackground::new(Color::zero()));
+
+    // the candela stay as they are in the file, this is the exposure turning the lux on
+    // the walls into pixel values
+    let exposure = Color::new(0.01, 0.01, 0.01);
+
+    for x in [-3., 0., 3.] {
+        let mut downlight = PointLight::new(Point3::new(x, 3.4, -1.6), exposure);
+        downlight.set_profile(
+            profile.clone(),
+            Vec3::new(0., -1., 0.),
+            Vec3::new(1., 0., 0.),
+        );
+        cam.lights.push(Arc::new(downlight));
+    }
+
+    let mut spot = SpotLight::new(
+        Point3::new(3., 3., 2.),
+        Point3::new(1.5, 0.5, 0.5),
+        exposure,
+        30.,
+        40.,
+    );
+    spot.set_profile(profile, Vec3::new(1., 0., 0.));
+    cam.lights.push(Arc::new(spot));
+
+    Ok((world, cam))
+}
+
+// metallic going from 0 to 1 along the back row, the other lobes one per sphere in the front
+pub fn principled() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new(Color::new(0.4, 0.4, 0.4))),
+    )));
+
+    for i in 0..5 {
+        let mut mat = Principled::new(Color::new(0.9, 0.6, 0.2));
+        mat.metallic = (i as f32 / 4.).into();
+        mat.roughness = 0.3.into();
+        world.add(Arc::new(Sphere::new(
+            Point3::new(2. * (i as f32 - 2.), 0.8, -1.2),
+            0.8,
+            Arc::new(mat),
+        )));
+    }
+
+    let mut plastic = Principled::new(Color::new(0.1, 0.3, 0.8));
+    plastic.roughness = 0.4.into();
+
+    let mut coated = Principled::new(Color::new(0.7, 0.05, 0.05));
+    coated.roughness = 0.8.into();
+    coated.clearcoat = 1.0.into();
+
+    let mut cloth = Principled::new(Color::new(0.3, 0.05, 0.3));
+    cloth.roughness = 1.0.into();
+    cloth.specular = 0.0.into();
+    cloth.sheen = 1.0.into();
+
+    let mut glass = Principled::new(Color::new(0.9, 1.0, 0.9));
+    glass.roughness = 0.1.into();
+    glass.transmission = 1.0.into();
+
+    // rough in the dark squares and polished in the light ones
+    let checker: Arc<dyn Texture + Sync + Send> = Arc::new(CheckerTexture::from_colors(
+        0.25,
+        Color::new(0.9, 0.9, 0.9),
+        Color::new(0.1, 0.1, 0.1),
+    ));
+    let mut textured = Principled::from_texture(checker.clone());
+    textured.roughness = Param::from(checker);
+    textured.clearcoat = 0.5.into();
+
+    let front: [Principled; 5] = [plastic, coated, cloth, glass, textured];
+    for (i, mat) in front.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(2. * (i as f32 - 2.), 0.8, 1.2),
+            0.8,
+            Arc::new(mat),
+        )));
+    }
+
+    let light = Arc::new(DiffuseLight::new(Color::new(10., 10., 10.)));
+    world.add(Arc::new(Sphere::new(Point3::new(-4., 9., 6.), 1.5, light)));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        35.0,
+        Point3::new(0., 5., 12.),
+        Point3::new(0., 0.5, 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(GradientBackground::new(
+        Vec3::new(0., 1., 0.),
+        Color::new(0.3, 0.3, 0.3),
+        Color::new(0.15, 0.2, 0.3),
+    ));
+
+    Ok((world, cam))
+}
+
+pub fn quads() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    let left_red = Arc::new(Lambertian::new(Color::new(1.0, 0.2, 0.2)));
+    let back_green = Arc::new(Lambertian::new(Color::new(0.2, 1.0, 0.2)));
+    let right_blue = Arc::new(Lambertian::new(Color::new(0.2, 0.2, 1.0)));
+    let upper_orange = Arc::new(Lambertian::new(Color::new(1.0, 0.5, 0.0)));
+    let lower_teal = Arc::new(Lambertian::new(Color::new(0.2, 0.8, 0.8)));
+
+    world.add(Arc::new(Quad::new(
+        Point3::new(-3., -2., 5.),
+        Vec3::new(0., 0., -4.),
+        Vec3::new(0., 4., 0.),
+        left_red,
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-2., -2., 0.),
+        Vec3::new(4., 0., 0.),
+        Vec3::new(0., 4., 0.),
+        back_green,
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(3., -2., 1.),
+        Vec3::new(0., 0., 4.),
+        Vec3::new(0., 4., 0.),
+        right_blue,
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-2., 3., 1.),
+        Vec3::new(4., 0., 0.),
+        Vec3::new(0., 0., 4.),
+        upper_orange,
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-2., -3., 5.),
+        Vec3::new(4., 0., 0.),
+        Vec3::new(0., 0., -4.),
+        lower_teal,
+    )));
+
+    let cam = Camera::new(
+        1.0,
+        400,
+        100,
+        50,
+        80.0,
+        Point3::new(0., 0., 9.),
+        Point3::new(0., 0., 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    Ok((world, cam))
+}
+
+// the walls and ceiling light of the Cornell box, without anything inside
+fn cornell_room() -> HittableList {
+    let mut world = HittableList::new();
+
+    let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::new(Color::new(15., 15., 15.)));
+
+    world.add(Arc::new(Quad::new(
+        Point3::new(555., 0., 0.),
+        Vec3::new(0., 555., 0.),
+        Vec3::new(0., 0., 555.),
+        green,
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(0., 0., 0.),
+        Vec3::new(0., 555., 0.),
+        Vec3::new(0., 0., 555.),
+        red,
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(343., 554., 332.),
+        Vec3::new(-130., 0., 0.),
+        Vec3::new(0., 0., -105.),
+        light,
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(0., 0., 0.),
+        Vec3::new(555., 0., 0.),
+        Vec3::new(0., 0., 555.),
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(555., 555., 555.),
+        Vec3::new(-555., 0., 0.),
+        Vec3::new(0., 0., -555.),
+        white.clone(),
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(0., 0., 555.),
+        Vec3::new(555., 0., 0.),
+        Vec3::new(0., 555., 0.),
+        white.clone(),
+    )));
+
+    world
+}
+
+// the tall and the short block, rotated and placed in the room
+fn cornell_blocks(
+    mat: Arc<dyn Material + Sync + Send>,
+) -> Result<[Arc<dyn Hittable + Sync + Send>; 2]> {
+    let box1 = make_box(
+        &Point3::new(0., 0., 0.),
+        &Point3::new(165., 330., 165.),
+        mat.clone(),
+    );
+    let box2 = make_box(
+        &Point3::new(0., 0., 0.),
+        &Point3::new(165., 165., 165.),
+        mat,
+    );
+
+    Ok([
+        Arc::new(Transform::new(
+            Arc::new(box1),
+            Mat4::translation(&Vec3::new(265., 0., 295.)) * Mat4::rotation_y(15.),
+        )?),
+        Arc::new(Transform::new(
+            Arc::new(box2),
+            Mat4::translation(&Vec3::new(130., 0., 65.)) * Mat4::rotation_y(-18.),
+        )?),
+    ])
+}
+
+fn cornell_camera() -> Camera {
+    let mut cam = Camera::new(
+        1.0,
+        600,
+        200,
+        50,
+        40.0,
+        Point3::new(278., 278., -800.),
+        Point3::new(278., 278., 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(SolidBackground::new(Color::zero()));
+
+    cam
+}
+
+pub fn cornell_box() -> Result<(HittableList, Camera)> {
+    let mut world = cornell_room();
+
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    for block in cornell_blocks(white)? {
+        world.add(block);
+    }
+
+    Ok((world, cornell_camera()))
+}
+
+pub fn cornell_smoke() -> Result<(HittableList, Camera)> {
+    let mut world = cornell_room();
+
+    let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let [tall, short] = cornell_blocks(white)?;
+    world.add(Arc::new(ConstantMedium::new(tall, 0.01, Color::zero())));
+    world.add(Arc::new(ConstantMedium::new(short, 0.01, Color::one())));
+
+    Ok((world, cornell_camera()))
+}
+
+// latitude-longitude tessellation of a sphere, with per vertex normals when `smooth` is set
+fn uv_sphere_mesh(
+    centre: Point3,
+    radius: f32,
+    segments: u32,
+    rings: u32,
+    smooth: bool,
+    mat: Arc<dyn Material + Sync + Send>,
+) -> Result<TriangleMesh> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+
+    for ring in 0..=rings {
+        let theta = f32::consts::PI * ring as f32 / rings as f32;
+        for seg in 0..=segments {
+            let phi = 2. * f32::consts::PI * seg as f32 / segments as f32;
+            let n = Vec3::new(
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                -theta.sin() * phi.sin(),
+            );
+            positions.push(centre + radius * n);
+            normals.push(n);
+            uvs.push((
+                seg as f32 / segments as f32,
+                1. - ring as f32 / rings as f32,
+            ));
+        }
+    }
+
+    let mut indices = Vec::new();
+    let stride = segments + 1;
+    for ring in 0..rings {
+        for seg in 0..segments {
+            let a = ring * stride + seg;
+            let b = a + stride;
+            if ring != 0 {
+                indices.push([a, b, a + 1]);
+            }
+            if ring != rings - 1 {
+                indices.push([a + 1, b, b + 1]);
+            }
+        }
+    }
+
+    if !smooth {
+        normals.clear();
+    }
+
+    TriangleMesh::new(positions, normals, uvs, Vec::new(), indices, mat)
+}
+
+// a neon arc and a glowing ball made of triangles as the only lights, each face of them
+// sampled as an area light
+pub fn mesh_lights() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Quad::new(
+        Point3::new(-10., 0., -10.),
+        Vec3::new(20., 0., 0.),
+        Vec3::new(0., 0., 20.),
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-10., 0., -3.),
+        Vec3::new(20., 0., 0.),
+        Vec3::new(0., 10., 0.),
+        Arc::new(Lambertian::new(Color::new(0.4, 0.4, 0.45))),
+    )));
+
+    for (i, color) in [
+        Color::new(0.8, 0.3, 0.3),
+        Color::new(0.8, 0.8, 0.8),
+        Color::new(0.3, 0.3, 0.8),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(2.2 * (i as f32 - 1.), 0.6, 0.5),
+            0.6,
+            Arc::new(Lambertian::new(color)),
+        )));
+    }
+
+    // a flat ribbon bent into a half circle in front of the back wall
+    let (segments, radius, width) = (48, 2.6, 0.08);
+    let mut positions = Vec::new();
+    for i in 0..=segments {
+        let angle = f32::consts::PI * i as f32 / segments as f32;
+        let (sin, cos) = angle.sin_cos();
+        positions.push(Point3::new(
+            (radius - width) * cos,
+            0.3 + (radius - width) * sin,
+            -2.9,
+        ));
+        positions.push(Point3::new(
+            (radius + width) * cos,
+            0.3 + (radius + width) * sin,
+            -2.9,
+        ));
+    }
+    let indices = (0..segments)
+        .flat_map(|i| {
+            let a = 2 * i;
+            [[a, a + 1, a + 2], [a + 1, a + 3, a + 2]]
+        })
+        .collect();
+    let neon = TriangleMesh::new(
+        positions,
+        Vec::new(),
+        Vec::new(),
+        Vec::new(),
+        indices,
+        Arc::new(DiffuseLight::new(Color::new(2., 12., 14.))),
+    )?;
+    world.add(Arc::new(BVHNode::from_hittable_list(
+        neon.into_hittable_list(),
+    )));
+
+    // the ball is placed by a transform, its lights are sampled through it
+    let ball = uv_sphere_mesh(
+        Point3::new(0., 0., 0.),
+        1.,
+        12,
+        6,
+        false,
+        Arc::new(DiffuseLight::new(Color::new(12., 6., 2.))),
+    )?;
+    world.add(Arc::new(Transform::new(
+        Arc::new(BVHNode::from_hittable_list(ball.into_hittable_list())),
+        Mat4::translation(&Vec3::new(1.2, 1.9, 0.)) * Mat4::scaling(&Vec3::new(0.25, 0.25, 0.25)),
+    )?));
+
+    let mut cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        40.0,
+        Point3::new(0., 2., 9.),
+        Point3::new(0., 1.2, 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+    cam.background = Arc::new(SolidBackground::new(Color::zero()));
+
+    Ok((world, cam))
+}
+
+pub fn meshes() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::from_colors(
+        0.5,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    world.add(Arc::new(Quad::new(
+        Point3::new(-10., 0., -10.),
+        Vec3::new(20., 0., 0.),
+        Vec3::new(0., 0., 20.),
+        Arc::new(Lambertian::from_texture(checker)),
+    )));
+
+    let flat = uv_sphere_mesh(
+        Point3::new(-1.1, 1., 0.),
+        1.,
+        16,
+        8,
+        false,
+        Arc::new(Lambertian::new(Color::new(0.7, 0.3, 0.2))),
+    )?;
+    let smooth = uv_sphere_mesh(
+        Point3::new(1.1, 1., 0.),
+        1.,
+        16,
+        8,
+        true,
+        Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.0)),
+    )?;
+
+    let mut objects = HittableList::new();
+    for mesh in [flat, smooth] {
+        for tri in mesh.into_hittable_list().objects {
+            objects.add(tri);
+        }
+    }
+    world.add(Arc::new(BVHNode::from_hittable_list(objects)));
+
+    let cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        30.0,
+        Point3::new(0., 2., 8.),
+        Point3::new(0., 1., 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    Ok((world, cam))
+}
+
+pub fn obj_model(path: &str) -> Result<(HittableList, Camera)> {
+    let model = load_obj(path, Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))))?;
+    Ok(framed_model(model))
+}
+
+// uses the vertex colors of the mesh when it has them
+pub fn ply_model(path: &str) -> Result<(HittableList, Camera)> {
+    let colors = Arc::new(VertexColorTexture::new(Color::new(0.8, 0.8, 0.8)));
+    let mesh = load_ply(path, Arc::new(Lambertian::from_texture(colors)))?;
+    Ok(framed_model(mesh.into_hittable_list()))
+}
+
+// puts the model in a BVH and frames the camera on its bounding box
+fn framed_model(model: HittableList) -> (HittableList, Camera) {
+    let model = BVHNode::from_hittable_list(model);
+
+    let bbox = model.bounding_box();
+    let centre = Point3::new(
+        0.5 * (bbox.x.min + bbox.x.max),
+        0.5 * (bbox.y.min + bbox.y.max),
+        0.5 * (bbox.z.min + bbox.z.max),
+    );
+    let radius = 0.5 * Vec3::new(bbox.x.size(), bbox.y.size(), bbox.z.size()).len();
+
+    let mut world = HittableList::new();
+    world.add(Arc::new(model));
+
+    let cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        40.0,
+        centre + Vec3::new(0.5, 0.6, 2.5) * radius,
+        centre,
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    (world, cam)
+}
+
+// one mesh placed several times with different transforms
+pub fn instances() -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Quad::new(
+        Point3::new(-10., 0., -10.),
+        Vec3::new(20., 0., 0.),
+        Vec3::new(0., 0., 20.),
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+
+    let mesh = uv_sphere_mesh(
+        Point3::new(0., 0., 0.),
+        1.,
+        32,
+        16,
+        true,
+        Arc::new(Metal::new(Color::new(0.8, 0.6, 0.4), 0.1)),
+    )?;
+    let mesh: Arc<dyn Hittable + Sync + Send> =
+        Arc::new(BVHNode::from_hittable_list(mesh.into_hittable_list()));
+
+    for i in 0..5 {
+        let x = -4. + 2. * i as f32;
+        let squash = 0.4 + 0.15 * i as f32;
+        let matrix = Mat4::translation(&Vec3::new(x, squash, 0.))
+            * Mat4::rotation_z(20. * i as f32)
+            * Mat4::scaling(&Vec3::new(0.8, squash, 0.8));
+
+        world.add(Arc::new(Transform::new(mesh.clone(), matrix)?));
+    }
+
+    world.add(Arc::new(Transform::translate(
+        Arc::new(make_box(
+            &Point3::new(-0.5, 0., -0.5),
+            &Point3::new(0.5, 1., 0.5),
+            Arc::new(Lambertian::new(Color::new(0.2, 0.4, 0.7))),
+        )),
+        &Vec3::new(0., 0., -3.),
+    )));
+
+    let world_bvh = BVHNode::from_hittable_list(world);
+    let mut world = HittableList::new();
+    world.add(Arc::new(world_bvh));
+
+    let cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        30.0,
+        Point3::new(0., 4., 12.),
+        Point3::new(0., 0.5, -1.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    Ok((world, cam))
+}
+
+// a procedural cloud with a glowing core, made of a few soft blobs
+pub fn cloud() -> Result<(HittableList, Camera)> {
+    let blobs = [
+        (Point3::new(0.5, 0.4, 0.5), 0.3),
+        (Point3::new(0.3, 0.5, 0.45), 0.2),
+        (Point3::new(0.7, 0.45, 0.55), 0.22),
+        (Point3::new(0.5, 0.65, 0.5), 0.2),
+    ];
+
+    let grid = DensityGrid::from_fn(64, 64, 64, |p| {
+        let density = blobs
+            .iter()
+            .map(|(centre, radius)| {
+                let d = (*p - *centre).len() / radius;
+                (1. - d * d).max(0.)
+            })
+            .sum::<f32>();
+
+        let core = (1. - (*p - blobs[0].0).len() / 0.15).max(0.);
+        let emission = Color::new(8., 3., 0.5) * core * core;
+
+        Voxel::new(density, Color::new(0.9, 0.9, 0.9), emission)
+    })?;
+
+    volume_scene(grid, 6.)
+}
+
+// a grid loaded from a raw file, see DensityGrid::open for the format
+pub fn volume_grid(path: &str) -> Result<(HittableList, Camera)> {
+    volume_scene(DensityGrid::open(path)?, 2.)
+}
+
+fn volume_scene(grid: DensityGrid, density_scale: f32) -> Result<(HittableList, Camera)> {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0., -1000., 0.),
+        1000.,
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    world.add(Arc::new(GridMedium::new(
+        Arc::new(grid),
+        &Point3::new(-2., 0., -2.),
+        &Point3::new(2., 4., 2.),
+        density_scale,
+    )?));
+
+    let cam = Camera::new(
+        16.0 / 9.0,
+        400,
+        100,
+        50,
+        40.0,
+        Point3::new(0., 3., 9.),
+        Point3::new(0., 1.8, 0.),
+        Vec3::new(0., 1., 0.),
+        0.0,
+        10.0,
+    );
+
+    Ok((world, cam))
+}
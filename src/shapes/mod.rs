@@ -1 +1,3 @@
+pub mod quad;
 pub mod sphere;
+pub mod triangle;
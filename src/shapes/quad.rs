@@ -0,0 +1,166 @@
+use std::{f32, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, HittableList},
+    light::{LightBounds, mean_emission},
+    material::Material,
+    ray::{Interval, Ray},
+    vector::{Point3, Vec3},
+};
+
+// parallelogram spanned by the edges `u` and `v` from the corner `q`
+pub struct Quad {
+    pub q: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub mat: Arc<dyn Material + Sync + Send>,
+    pub bbox: Aabb,
+    normal: Vec3, // unit normal of the supporting plane
+    d: f32,       // plane constant, normal . p = d
+    w: Vec3,      // n / (n . n), used to get the planar coordinates of a hit
+    area: f32,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, mat: Arc<dyn Material + Sync + Send>) -> Self {
+        // the box of the diagonals, padded since it is flat along at least one axis
+        // when the quad is axis aligned
+        let bbox = Aabb::enclose(&Aabb::new(&q, &(q + u + v)), &Aabb::new(&(q + u), &(q + v)))
+            .pad_to_minimums();
+
+        let n = u.cross(&v);
+        let normal = n.unit_vec();
+        let d = normal.dot(&q);
+        let w = n / n.dot(&n);
+        let area = n.len();
+
+        Quad {
+            q,
+            u,
+            v,
+            mat,
+            bbox,
+            normal,
+            d,
+            w,
+            area,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    #[inline(always)]
+    fn hit(&self, r: &Ray, intvl: &Interval) -> Option<HitRecord> {
+        let denom = self.normal.dot(&r.dir);
+
+        // no hit if the ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(&r.origin)) / denom;
+        if !intvl.surrounds(t) {
+            return None;
+        }
+
+        // check the hit lies within the quad using its planar coordinates
+        let p = r.at(t);
+        let planar_hit = p - self.q;
+        let alpha = self.w.dot(&planar_hit.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar_hit));
+
+        let unit = Interval::new(0., 1.);
+        if !unit.contains(alpha) || !unit.contains(beta) {
+            return None;
+        }
+
+        let face_normal = HitRecord::calc_face_normal(r, &self.normal);
+
+        Some(HitRecord::new(
+            t,
+            p,
+            face_normal,
+            alpha,
+            beta,
+            self.mat.clone(),
+        ))
+    }
+
+    #[inline(always)]
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    // uniform over the area, converted to a density over solid angle from the origin
+    #[inline(always)]
+    fn pdf_value(&self, r: &Ray) -> f32 {
+        let Some(rec) = self.hit(r, &Interval::new(0.001, f32::INFINITY)) else {
+            return 0.;
+        };
+
+        let dist_squared = rec.t * rec.t * r.dir.len_squared();
+        let cosine = (r.dir.dot(&self.normal) / r.dir.len()).abs();
+
+        dist_squared / (cosine * self.area)
+    }
+
+    #[inline(always)]
+    fn random(&self, origin: &Point3, _time: f32) -> Vec3 {
+        let p = self.q + fastrand::f32() * self.u + fastrand::f32() * self.v;
+        p - *origin
+    }
+
+    #[inline(always)]
+    fn is_light(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    // light leaves both sides, emission averaged over a 3x3 grid of points
+    fn light_bounds(&self) -> Option<LightBounds> {
+        let mut points = Vec::with_capacity(9);
+        for a in [1. / 6., 0.5, 5. / 6.] {
+            for b in [1. / 6., 0.5, 5. / 6.] {
+                points.push((self.q + a * self.u + b * self.v, self.normal, a, b));
+            }
+        }
+
+        let phi = 2. * f32::consts::PI * self.area * mean_emission(&self.mat, &points);
+
+        Some(LightBounds::new(
+            self.bbox.clone(),
+            phi,
+            self.normal,
+            1.,
+            0.,
+            true,
+        ))
+    }
+}
+
+// the six sides of the axis aligned box with opposite corners `a` and `b`
+pub fn make_box(a: &Point3, b: &Point3, mat: Arc<dyn Material + Sync + Send>) -> HittableList {
+    let mut sides = HittableList::new();
+
+    let min = Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+    let dx = Vec3::new(max.x - min.x, 0., 0.);
+    let dy = Vec3::new(0., max.y - min.y, 0.);
+    let dz = Vec3::new(0., 0., max.z - min.z);
+
+    let faces = [
+        (Point3::new(min.x, min.y, max.z), dx, dy),  // front
+        (Point3::new(max.x, min.y, max.z), -dz, dy), // right
+        (Point3::new(max.x, min.y, min.z), -dx, dy), // back
+        (Point3::new(min.x, min.y, min.z), dz, dy),  // left
+        (Point3::new(min.x, max.y, max.z), dx, -dz), // top
+        (Point3::new(min.x, min.y, min.z), dx, dz),  // bottom
+    ];
+
+    for (q, u, v) in faces {
+        sides.add(Arc::new(Quad::new(q, u, v, mat.clone())));
+    }
+
+    sides
+}
@@ -1,15 +1,17 @@
-use std::sync::Arc;
+use std::{f32, sync::Arc};
 
 use crate::{
     aabb::Aabb,
     hittable::{HitRecord, Hittable},
+    light::{LightBounds, mean_emission},
     material::Material,
     ray::{Interval, Ray},
-    vector::{Point3, Vec3},
+    vector::{Onb, Point3, Vec3},
 };
 
 pub struct Sphere {
-    pub centre: Point3,
+    pub centre: Point3, // centre at time 0
+    pub motion: Vec3,   // displacement of the centre between time 0 and time 1
     pub radius: f32,
     pub mat: Arc<dyn Material + Sync + Send>,
     pub bbox: Aabb,
@@ -17,22 +19,54 @@ pub struct Sphere {
 
 impl Sphere {
     pub fn new(centre: Point3, radius: f32, mat: Arc<dyn Material + Sync + Send>) -> Self {
+        Self::new_moving(centre, centre, radius, mat)
+    }
+
+    // sphere moving linearly from `centre0` at time 0 to `centre1` at time 1
+    pub fn new_moving(
+        centre0: Point3,
+        centre1: Point3,
+        radius: f32,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Self {
         let radius = radius.max(0.);
         let rvec = Vec3::new(radius, radius, radius);
-        let bbox = Aabb::new(&(centre - rvec), &(centre + rvec));
+
+        // enclose the whole swept path
+        let box0 = Aabb::new(&(centre0 - rvec), &(centre0 + rvec));
+        let box1 = Aabb::new(&(centre1 - rvec), &(centre1 + rvec));
+        let bbox = Aabb::enclose(&box0, &box1);
+
         Sphere {
-            centre,
+            centre: centre0,
+            motion: centre1 - centre0,
             radius,
             mat,
             bbox,
         }
     }
+
+    #[inline(always)]
+    pub fn centre_at(&self, time: f32) -> Point3 {
+        self.centre + time * self.motion
+    }
+
+    // (u, v) for a point on the unit sphere: u is the angle around the y axis from x = -1,
+    // v is the angle from y = -1 to y = +1, both normalised to [0, 1]
+    #[inline(always)]
+    fn get_sphere_uv(p: &Point3) -> (f32, f32) {
+        let theta = (-p.y).acos();
+        let phi = (-p.z).atan2(p.x) + f32::consts::PI;
+
+        (phi / (2. * f32::consts::PI), theta / f32::consts::PI)
+    }
 }
 
 impl Hittable for Sphere {
     #[inline(always)]
     fn hit(&self, r: &Ray, intvl: &Interval) -> Option<HitRecord> {
-        let oc = self.centre - r.origin;
+        let centre = self.centre_at(r.time);
+        let oc = centre - r.origin;
         let a = r.dir.len_squared();
         let h = r.dir.dot(&oc);
         let c = oc.len_squared() - self.radius * self.radius;
@@ -55,14 +89,81 @@ impl Hittable for Sphere {
 
         let t = root;
         let p = r.at(t);
-        let outward_normal = (p - self.centre) / self.radius;
+        let outward_normal = (p - centre) / self.radius;
         let face_normal = HitRecord::calc_face_normal(r, &outward_normal);
+        let (u, v) = Self::get_sphere_uv(&outward_normal);
+
+        Some(HitRecord::new(t, p, face_normal, u, v, self.mat.clone()))
+    }
+
+    #[inline(always)]
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    // uniform over the cone of directions the sphere covers as seen from the origin,
+    // or over all directions from inside it
+    #[inline(always)]
+    fn pdf_value(&self, r: &Ray) -> f32 {
+        if self.hit(r, &Interval::new(0.001, f32::INFINITY)).is_none() {
+            return 0.;
+        }
 
-        Some(HitRecord::new(t, p, face_normal, self.mat.clone()))
+        let dist_squared = (self.centre_at(r.time) - r.origin).len_squared();
+        let radius_squared = self.radius * self.radius;
+        if dist_squared <= radius_squared {
+            return 1. / (4. * f32::consts::PI);
+        }
+
+        let cos_max = (1. - radius_squared / dist_squared).sqrt();
+        1. / (2. * f32::consts::PI * (1. - cos_max))
     }
 
     #[inline(always)]
-    fn bounding_box(&self) -> &Aabb {
-        &self.bbox
+    fn random(&self, origin: &Point3, time: f32) -> Vec3 {
+        let dir = self.centre_at(time) - *origin;
+        let dist_squared = dir.len_squared();
+        let radius_squared = self.radius * self.radius;
+        if dist_squared <= radius_squared {
+            return Vec3::random_unit_vec();
+        }
+
+        let cos_max = (1. - radius_squared / dist_squared).sqrt();
+        Onb::new(&dir).transform(&Vec3::random_in_cone(cos_max))
+    }
+
+    #[inline(always)]
+    fn is_light(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    // shines every way, over the whole path when it moves
+    fn light_bounds(&self) -> Option<LightBounds> {
+        let points: Vec<_> = [
+            Vec3::new(1., 0., 0.),
+            Vec3::new(-1., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            Vec3::new(0., -1., 0.),
+            Vec3::new(0., 0., 1.),
+            Vec3::new(0., 0., -1.),
+        ]
+        .into_iter()
+        .map(|n| {
+            let (u, v) = Self::get_sphere_uv(&n);
+            (self.centre + self.radius * n, n, u, v)
+        })
+        .collect();
+
+        let area = 4. * f32::consts::PI * self.radius * self.radius;
+        let phi = f32::consts::PI * area * mean_emission(&self.mat, &points);
+
+        Some(LightBounds::new(
+            self.bbox.clone(),
+            phi,
+            Vec3::new(0., 0., 1.),
+            -1.,
+            0.,
+            false,
+        ))
     }
 }
@@ -0,0 +1,302 @@
+use std::{f32, sync::Arc};
+
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    hittable::{HitRecord, Hittable, HittableList},
+    light::{LightBounds, mean_emission},
+    material::Material,
+    ray::{FaceNormal, Interval, Ray},
+    sampling::{SphericalTriangle, spherical_triangle_area},
+    vector::{Point3, Vec3},
+};
+
+use anyhow::{Result, bail};
+
+// triangles covering a solid angle in this range are sampled uniformly over it, tinier
+// ones are too prone to round-off and huge ones are better sampled by area
+const MIN_SPHERICAL_SAMPLE_AREA: f32 = 3e-4;
+const MAX_SPHERICAL_SAMPLE_AREA: f32 = 6.22;
+
+// indexed triangle mesh, the vertex buffers are shared by all of its triangles
+// `normals`, `uvs` and `colors` are either empty or hold one entry per vertex, which
+// `new` checks along with the indices
+pub struct TriangleMesh {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(f32, f32)>,
+    pub colors: Vec<Color>,
+    pub indices: Vec<[u32; 3]>,
+    pub mat: Arc<dyn Material + Sync + Send>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f32, f32)>,
+        colors: Vec<Color>,
+        indices: Vec<[u32; 3]>,
+        mat: Arc<dyn Material + Sync + Send>,
+    ) -> Result<Self> {
+        let n = positions.len();
+        for (name, len) in [
+            ("normals", normals.len()),
+            ("uvs", uvs.len()),
+            ("colors", colors.len()),
+        ] {
+            if len != 0 && len != n {
+                bail!("mesh has {len} {name} for {n} vertices");
+            }
+        }
+        if let Some(&[a, b, c]) = indices
+            .iter()
+            .find(|face| face.iter().any(|&i| i as usize >= n))
+        {
+            bail!("face ({a}, {b}, {c}) indexes past the {n} vertices of the mesh");
+        }
+
+        Ok(Self {
+            positions,
+            normals,
+            uvs,
+            colors,
+            indices,
+            mat,
+        })
+    }
+
+    #[inline(always)]
+    pub fn face_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    // one Triangle per face, ready to be put in a BVH
+    pub fn into_hittable_list(self) -> HittableList {
+        let mesh = Arc::new(self);
+        let mut list = HittableList::new();
+
+        for face in 0..mesh.face_count() {
+            list.add(Arc::new(Triangle::new(mesh.clone(), face as u32)));
+        }
+
+        list
+    }
+}
+
+// a single face of a TriangleMesh
+pub struct Triangle {
+    pub mesh: Arc<TriangleMesh>,
+    pub face: u32,
+}
+
+impl Triangle {
+    #[inline(always)]
+    pub fn new(mesh: Arc<TriangleMesh>, face: u32) -> Self {
+        Self { mesh, face }
+    }
+
+    #[inline(always)]
+    fn vertex_indices(&self) -> [usize; 3] {
+        self.mesh.indices[self.face as usize].map(|i| i as usize)
+    }
+
+    #[inline(always)]
+    fn vertices(&self) -> [Point3; 3] {
+        self.vertex_indices().map(|i| self.mesh.positions[i])
+    }
+
+    #[inline(always)]
+    fn area(&self) -> f32 {
+        let [p0, p1, p2] = self.vertices();
+        0.5 * (p1 - p0).cross(&(p2 - p0)).len()
+    }
+
+    // the triangle as seen from `origin` and the solid angle it covers, when directions
+    // towards it are drawn uniformly over that rather than over its area, `random` and
+    // `pdf_value` both decide here so they always agree
+    #[inline(always)]
+    fn spherical_sampling(&self, origin: &Point3) -> Option<(SphericalTriangle, f32)> {
+        let [a, b, c] = self.vertices().map(|p| (p - *origin).unit_vec());
+        let area = spherical_triangle_area(&a, &b, &c);
+        if !(MIN_SPHERICAL_SAMPLE_AREA..=MAX_SPHERICAL_SAMPLE_AREA).contains(&area) {
+            return None;
+        }
+
+        Some((SphericalTriangle::new(&a, &b, &c)?, area))
+    }
+}
+
+impl Hittable for Triangle {
+    // Möller–Trumbore intersection
+    #[inline(always)]
+    fn hit(&self, r: &Ray, intvl: &Interval) -> Option<HitRecord> {
+        let [i0, i1, i2] = self.vertex_indices();
+        let p0 = self.mesh.positions[i0];
+        let p1 = self.mesh.positions[i1];
+        let p2 = self.mesh.positions[i2];
+
+        let e1 = p1 - p0;
+        let e2 = p2 - p0;
+
+        let pvec = r.dir.cross(&e2);
+        let det = e1.dot(&pvec);
+
+        // the ray is parallel to the triangle
+        if det.abs() < 1e-12 {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let tvec = r.origin - p0;
+        let b1 = tvec.dot(&pvec) * inv_det;
+        if !(0. ..=1.).contains(&b1) {
+            return None;
+        }
+
+        let qvec = tvec.cross(&e1);
+        let b2 = r.dir.dot(&qvec) * inv_det;
+        if b2 < 0. || b1 + b2 > 1. {
+            return None;
+        }
+
+        let t = e2.dot(&qvec) * inv_det;
+        if !intvl.surrounds(t) {
+            return None;
+        }
+
+        let b0 = 1. - b1 - b2;
+        let p = r.at(t);
+
+        let mut geometric_normal = e1.cross(&e2).unit_vec();
+
+        // interpolate the vertex normals if there are any, and make the geometric
+        // normal agree with them so both sides of the surface are consistent
+        let normal = if self.mesh.normals.is_empty() {
+            geometric_normal
+        } else {
+            let n = self.mesh.normals[i0] * b0
+                + self.mesh.normals[i1] * b1
+                + self.mesh.normals[i2] * b2;
+            let n = n.unit_vec();
+
+            if n.dot(&geometric_normal) < 0. {
+                geometric_normal = -geometric_normal;
+            }
+
+            n
+        };
+
+        let face_normal = if r.dir.dot(&geometric_normal) < 0. {
+            FaceNormal::Front(normal)
+        } else {
+            FaceNormal::Back(-normal)
+        };
+
+        let (u, v) = if self.mesh.uvs.is_empty() {
+            (b1, b2)
+        } else {
+            let (uv0, uv1, uv2) = (self.mesh.uvs[i0], self.mesh.uvs[i1], self.mesh.uvs[i2]);
+            (
+                b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+                b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+            )
+        };
+
+        let mut rec = HitRecord::new(t, p, face_normal, u, v, self.mesh.mat.clone());
+
+        if !self.mesh.colors.is_empty() {
+            let colors = &self.mesh.colors;
+            rec.vertex_color = Some(b0 * colors[i0] + b1 * colors[i1] + b2 * colors[i2]);
+        }
+
+        Some(rec)
+    }
+
+    // worked out from the vertices each time rather than stored for every face, it is only
+    // asked for while building
+    #[inline(always)]
+    fn bounding_box(&self) -> Aabb {
+        let [p0, p1, p2] = self.vertices();
+        Aabb::enclose(&Aabb::new(&p0, &p1), &Aabb::new(&p0, &p2)).pad_to_minimums()
+    }
+
+    // uniform over the solid angle the triangle covers from the origin when that is
+    // neither tiny nor huge, otherwise uniform over its area converted to solid angle
+    #[inline(always)]
+    fn pdf_value(&self, r: &Ray) -> f32 {
+        let Some(rec) = self.hit(r, &Interval::new(0.001, f32::INFINITY)) else {
+            return 0.;
+        };
+
+        if let Some((_, area)) = self.spherical_sampling(&r.origin) {
+            return 1. / area;
+        }
+
+        let [p0, p1, p2] = self.vertices();
+        let n = (p1 - p0).cross(&(p2 - p0));
+        let dist_squared = rec.t * rec.t * r.dir.len_squared();
+        let cosine = (r.dir.dot(&n) / (r.dir.len() * n.len())).abs();
+
+        dist_squared / (cosine * self.area())
+    }
+
+    #[inline(always)]
+    fn random(&self, origin: &Point3, _time: f32) -> Vec3 {
+        if let Some((triangle, _)) = self.spherical_sampling(origin) {
+            return triangle.sample(fastrand::f32(), fastrand::f32());
+        }
+
+        // uniform barycentrics
+        let [p0, p1, p2] = self.vertices();
+        let (s, t) = (fastrand::f32().sqrt(), fastrand::f32());
+        let (b1, b2) = (s * (1. - t), s * t);
+        let p = p0 + b1 * (p1 - p0) + b2 * (p2 - p0);
+        p - *origin
+    }
+
+    #[inline(always)]
+    fn is_light(&self) -> bool {
+        self.mesh.mat.is_emissive()
+    }
+
+    // light leaves both sides, emission averaged over the corners and the centre
+    fn light_bounds(&self) -> Option<LightBounds> {
+        let [i0, i1, i2] = self.vertex_indices();
+        let [p0, p1, p2] = self.vertices();
+        let normal = (p1 - p0).cross(&(p2 - p0)).unit_vec();
+
+        let uv = |i: usize, b: (f32, f32)| {
+            if self.mesh.uvs.is_empty() {
+                b
+            } else {
+                self.mesh.uvs[i]
+            }
+        };
+        let centre_uv = if self.mesh.uvs.is_empty() {
+            (1. / 3., 1. / 3.)
+        } else {
+            let (uv0, uv1, uv2) = (self.mesh.uvs[i0], self.mesh.uvs[i1], self.mesh.uvs[i2]);
+            ((uv0.0 + uv1.0 + uv2.0) / 3., (uv0.1 + uv1.1 + uv2.1) / 3.)
+        };
+
+        let points = [
+            (p0, uv(i0, (0., 0.))),
+            (p1, uv(i1, (1., 0.))),
+            (p2, uv(i2, (0., 1.))),
+            ((p0 + p1 + p2) / 3., centre_uv),
+        ]
+        .map(|(p, (u, v))| (p, normal, u, v));
+
+        let phi = 2. * f32::consts::PI * self.area() * mean_emission(&self.mesh.mat, &points);
+
+        Some(LightBounds::new(
+            self.bounding_box(),
+            phi,
+            normal,
+            1.,
+            0.,
+            true,
+        ))
+    }
+}
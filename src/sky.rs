@@ -0,0 +1,266 @@
+use std::f32;
+
+use crate::{
+    background::Background, color::Color, light::DirectionalLight, ray::Ray, vector::Vec3,
+};
+
+// renders the sky's radiance, in kcd/m^2, at around 1 for a clear midday sky
+const UNIT_SCALE: f32 = 0.05;
+
+// illuminance from the sun above the atmosphere, in klux
+const SOLAR_ILLUMINANCE: f32 = 127.5;
+
+// where the sun is in the sky, in degrees: elevation above the horizon and azimuth
+// clockwise from north, with north along -z and east along +x
+#[derive(Clone, Copy)]
+pub struct SunPosition {
+    pub elevation: f32,
+    pub azimuth: f32,
+}
+
+#[allow(unused)]
+impl SunPosition {
+    #[inline(always)]
+    pub fn new(elevation: f32, azimuth: f32) -> Self {
+        Self { elevation, azimuth }
+    }
+
+    // sun position seen from `latitude` and `longitude` (degrees, north and east positive)
+    // on the given date at `utc_hours`, after NOAA's solar position approximation
+    pub fn at(
+        latitude: f32,
+        longitude: f32,
+        year: i32,
+        month: u32,
+        day: u32,
+        utc_hours: f32,
+    ) -> Self {
+        let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+        let days_before_month = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+        let month = month.clamp(1, 12) as usize;
+        let mut day_of_year = days_before_month[month - 1] + day as usize;
+        if leap && month > 2 {
+            day_of_year += 1;
+        }
+        let days_in_year = if leap { 366. } else { 365. };
+
+        // fractional year, in radians
+        let g = 2. * f32::consts::PI / days_in_year
+            * (day_of_year as f32 - 1. + (utc_hours - 12.) / 24.);
+
+        // equation of time in minutes, and declination in radians
+        let eq_time = 229.18
+            * (0.000075 + 0.001868 * g.cos()
+                - 0.032077 * g.sin()
+                - 0.014615 * (2. * g).cos()
+                - 0.040849 * (2. * g).sin());
+        let decl = 0.006918 - 0.399912 * g.cos() + 0.070257 * g.sin() - 0.006758 * (2. * g).cos()
+            + 0.000907 * (2. * g).sin()
+            - 0.002697 * (3. * g).cos()
+            + 0.00148 * (3. * g).sin();
+
+        // true solar time in minutes, and the hour angle
+        let solar_minutes = utc_hours * 60. + eq_time + 4. * longitude;
+        let hour_angle = (solar_minutes / 4. - 180.).to_radians();
+
+        let lat = latitude.to_radians();
+        let cos_zenith =
+            (lat.sin() * decl.sin() + lat.cos() * decl.cos() * hour_angle.cos()).clamp(-1., 1.);
+        let elevation = 90. - cos_zenith.acos().to_degrees();
+
+        let azimuth = hour_angle
+            .sin()
+            .atan2(hour_angle.cos() * lat.sin() - decl.tan() * lat.cos())
+            .to_degrees()
+            + 180.;
+
+        Self::new(elevation, azimuth.rem_euclid(360.))
+    }
+
+    // unit direction towards the sun
+    #[inline(always)]
+    pub fn dir(&self) -> Vec3 {
+        let (sin_e, cos_e) = self.elevation.to_radians().sin_cos();
+        let (sin_a, cos_a) = self.azimuth.to_radians().sin_cos();
+
+        Vec3::new(cos_e * sin_a, sin_e, -cos_e * cos_a)
+    }
+}
+
+// Preetham's analytic daylight model, for a clear sky of the given turbidity (2 is very
+// clear, 10 hazy) above a uniform ground, which is lit by the sky and sun it reflects
+pub struct PreethamSky {
+    pub turbidity: f32,
+    pub ground_albedo: Color,
+    pub intensity: f32, // multiplier on top of the default exposure
+    sun_dir: Vec3,
+    theta_s: f32,          // sun zenith angle, kept at or above the horizon
+    zenith: [f32; 3],      // Y, x and y straight up
+    coeffs: [[f32; 5]; 3], // Perez distribution A-E for Y, x and y
+    ground: Color,
+}
+
+#[allow(unused)]
+impl PreethamSky {
+    pub fn new(sun: SunPosition, turbidity: f32, ground_albedo: Color, intensity: f32) -> Self {
+        let t = turbidity.clamp(1.7, 10.);
+        let sun_dir = sun.dir();
+        let theta_s = sun_dir.y.clamp(0., 1.).acos();
+
+        let chi = (4. / 9. - t / 120.) * (f32::consts::PI - 2. * theta_s);
+        let zenith_y = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.);
+
+        let (th, th2, th3) = (theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let zenith_x = t * t * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let zenith_yc = t * t * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+
+        let coeffs = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let mut sky = Self {
+            turbidity: t,
+            ground_albedo,
+            intensity,
+            sun_dir,
+            theta_s,
+            zenith: [zenith_y, zenith_x, zenith_yc],
+            coeffs,
+            ground: Color::zero(),
+        };
+        sky.ground = sky.ground_radiance();
+
+        sky
+    }
+
+    // the sun disk matching this sky, its light dimmed and reddened by the air it crosses
+    pub fn sun_light(&self) -> DirectionalLight {
+        DirectionalLight::sun(self.sun_dir, self.sun_irradiance())
+    }
+
+    // Perez et al's all weather luminance distribution
+    #[inline(always)]
+    fn perez(c: &[f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+        let cos_gamma = gamma.cos();
+        (1. + c[0] * (c[1] / cos_theta.max(0.01)).exp())
+            * (1. + c[2] * (c[3] * gamma).exp() + c[4] * cos_gamma * cos_gamma)
+    }
+
+    // radiance from the sky along a direction at or above the horizon
+    #[inline(always)]
+    fn sky_radiance(&self, dir: &Vec3) -> Color {
+        let cos_theta = dir.y.max(0.);
+        let gamma = dir.dot(&self.sun_dir).clamp(-1., 1.).acos();
+
+        let mut yxy = [0.; 3];
+        for (i, v) in yxy.iter_mut().enumerate() {
+            let c = &self.coeffs[i];
+            *v = self.zenith[i] * Self::perez(c, cos_theta, gamma)
+                / Self::perez(c, 1., self.theta_s);
+        }
+
+        (UNIT_SCALE * self.intensity) * xyy_to_rgb(yxy[1], yxy[2], yxy[0])
+    }
+
+    // sunlight on a surface facing the sun, attenuated by Rayleigh and aerosol scattering
+    // along the optical air mass the light travels through
+    fn sun_irradiance(&self) -> Color {
+        if self.sun_dir.y <= 0. {
+            return Color::zero();
+        }
+
+        // Kasten and Young's relative air mass
+        let elevation = 90. - self.theta_s.to_degrees();
+        let air_mass = 1. / (self.theta_s.cos() + 0.50572 * (elevation + 6.07995).powf(-1.6364));
+
+        // Angstrom's turbidity formula for the aerosols
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |lambda_um: f32| {
+            let rayleigh = 0.008735 * lambda_um.powf(-4.08);
+            let aerosol = beta * lambda_um.powf(-1.3);
+            (-air_mass * (rayleigh + aerosol)).exp()
+        };
+
+        let t = Color::new(
+            transmittance(0.65),
+            transmittance(0.57),
+            transmittance(0.475),
+        );
+        (UNIT_SCALE * self.intensity * SOLAR_ILLUMINANCE) * t
+    }
+
+    // the ground is diffuse, lit by the whole sky dome and the sun
+    fn ground_radiance(&self) -> Color {
+        let (n_theta, n_phi) = (16, 32);
+        let d_theta = 0.5 * f32::consts::PI / n_theta as f32;
+        let d_phi = 2. * f32::consts::PI / n_phi as f32;
+
+        let mut irradiance = Color::zero();
+        for i in 0..n_theta {
+            let theta = (i as f32 + 0.5) * d_theta;
+            let (sin_t, cos_t) = theta.sin_cos();
+            for j in 0..n_phi {
+                let phi = (j as f32 + 0.5) * d_phi;
+                let dir = Vec3::new(sin_t * phi.cos(), cos_t, sin_t * phi.sin());
+                irradiance += (cos_t * sin_t * d_theta * d_phi) * self.sky_radiance(&dir);
+            }
+        }
+        irradiance += self.sun_dir.y.max(0.) * self.sun_irradiance();
+
+        (1. / f32::consts::PI) * self.ground_albedo * irradiance
+    }
+}
+
+impl Background for PreethamSky {
+    // the sun disk itself is left to `sun_light`, so it is not counted twice
+    #[inline(always)]
+    fn value(&self, r: &Ray) -> Color {
+        let dir = r.dir.unit_vec();
+        if dir.y < 0. {
+            return self.ground;
+        }
+
+        self.sky_radiance(&dir)
+    }
+}
+
+// CIE xyY to linear sRGB
+#[inline(always)]
+fn xyy_to_rgb(x: f32, y: f32, lum: f32) -> Color {
+    if y <= 0. {
+        return Color::zero();
+    }
+
+    let cx = x / y * lum;
+    let cz = (1. - x - y) / y * lum;
+
+    Color::new(
+        (3.2406 * cx - 1.5372 * lum - 0.4986 * cz).max(0.),
+        (-0.9689 * cx + 1.8758 * lum + 0.0415 * cz).max(0.),
+        (0.0557 * cx - 0.2040 * lum + 1.0570 * cz).max(0.),
+    )
+}
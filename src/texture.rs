@@ -0,0 +1,261 @@
+use std::{path::Path, sync::Arc};
+
+use crate::{color::Color, hittable::HitRecord, perlin::Perlin};
+
+use anyhow::Result;
+
+pub trait Texture {
+    fn value(&self, rec: &HitRecord) -> Color;
+}
+
+pub struct SolidColor {
+    pub albedo: Color,
+}
+
+impl SolidColor {
+    #[inline(always)]
+    pub const fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    #[inline(always)]
+    fn value(&self, _rec: &HitRecord) -> Color {
+        self.albedo
+    }
+}
+
+// 3D checker pattern, alternating between two textures every `scale` units along each axis
+pub struct CheckerTexture {
+    inv_scale: f32,
+    pub even: Arc<dyn Texture + Sync + Send>,
+    pub odd: Arc<dyn Texture + Sync + Send>,
+}
+
+impl CheckerTexture {
+    #[inline(always)]
+    pub fn new(
+        scale: f32,
+        even: Arc<dyn Texture + Sync + Send>,
+        odd: Arc<dyn Texture + Sync + Send>,
+    ) -> Self {
+        Self {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    #[inline(always)]
+    pub fn from_colors(scale: f32, even: Color, odd: Color) -> Self {
+        Self::new(
+            scale,
+            Arc::new(SolidColor::new(even)),
+            Arc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for CheckerTexture {
+    #[inline(always)]
+    fn value(&self, rec: &HitRecord) -> Color {
+        let x = (self.inv_scale * rec.p.x).floor() as i32;
+        let y = (self.inv_scale * rec.p.y).floor() as i32;
+        let z = (self.inv_scale * rec.p.z).floor() as i32;
+
+        if (x + y + z) % 2 == 0 {
+            self.even.value(rec)
+        } else {
+            self.odd.value(rec)
+        }
+    }
+}
+
+// looks up the (u, v) surface coordinates in an image, nearest pixel
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl ImageTexture {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let img = image::open(path)?.into_rgb8();
+        let (width, height) = img.dimensions();
+
+        // the renderer works in linear space and gamma corrects with a sqrt on output,
+        // so undo that gamma here
+        let pixels = img
+            .pixels()
+            .map(|px| {
+                let [r, g, b] = px.0.map(|c| {
+                    let c = c as f32 / 255.0;
+                    c * c
+                });
+                Color::new(r, g, b)
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    #[inline(always)]
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let x = x.min(self.width - 1);
+        let y = y.min(self.height - 1);
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+impl Texture for ImageTexture {
+    #[inline(always)]
+    fn value(&self, rec: &HitRecord) -> Color {
+        if self.width == 0 || self.height == 0 {
+            return Color::new(0., 1., 1.);
+        }
+
+        // clamp to [0, 1] and flip v to image coordinates
+        let u = rec.u.clamp(0.0, 1.0);
+        let v = 1.0 - rec.v.clamp(0.0, 1.0);
+
+        let x = (u * self.width as f32) as u32;
+        let y = (v * self.height as f32) as u32;
+
+        self.pixel(x, y)
+    }
+}
+
+// per-vertex colors of a mesh, interpolated across each face
+// surfaces without vertex colors get the fallback color
+pub struct VertexColorTexture {
+    pub fallback: Color,
+}
+
+impl VertexColorTexture {
+    #[inline(always)]
+    pub const fn new(fallback: Color) -> Self {
+        Self { fallback }
+    }
+}
+
+impl Texture for VertexColorTexture {
+    #[inline(always)]
+    fn value(&self, rec: &HitRecord) -> Color {
+        rec.vertex_color.unwrap_or(self.fallback)
+    }
+}
+
+// raw Perlin noise remapped to [0, 1], as a grey level times `color`
+pub struct NoiseTexture {
+    noise: Perlin,
+    pub scale: f32,
+    pub color: Color,
+}
+
+impl NoiseTexture {
+    pub fn new(seed: u64, scale: f32, color: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            color,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    #[inline(always)]
+    fn value(&self, rec: &HitRecord) -> Color {
+        let n = self.noise.noise(&(self.scale * rec.p));
+        0.5 * (1. + n) * self.color
+    }
+}
+
+// several octaves of noise, giving a rougher, cloudy look
+pub struct TurbulenceTexture {
+    noise: Perlin,
+    pub scale: f32,
+    pub depth: u32,
+    pub color: Color,
+}
+
+impl TurbulenceTexture {
+    pub fn new(seed: u64, scale: f32, depth: u32, color: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            depth,
+            color,
+        }
+    }
+}
+
+impl Texture for TurbulenceTexture {
+    #[inline(always)]
+    fn value(&self, rec: &HitRecord) -> Color {
+        self.noise.turb(&(self.scale * rec.p), self.depth) * self.color
+    }
+}
+
+// veins along z, a sine wave whose phase is disturbed by turbulence
+pub struct MarbleTexture {
+    noise: Perlin,
+    pub scale: f32,
+    pub base: Color,
+    pub vein: Color,
+}
+
+impl MarbleTexture {
+    pub fn new(seed: u64, scale: f32, base: Color, vein: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            base,
+            vein,
+        }
+    }
+}
+
+impl Texture for MarbleTexture {
+    #[inline(always)]
+    fn value(&self, rec: &HitRecord) -> Color {
+        let t = 0.5 * (1. + (self.scale * rec.p.z + 10. * self.noise.turb(&rec.p, 7)).sin());
+        t * self.base + (1. - t) * self.vein
+    }
+}
+
+// concentric growth rings around the y axis, wobbled by noise
+pub struct WoodTexture {
+    noise: Perlin,
+    pub rings: f32, // rings per unit distance from the axis
+    pub light: Color,
+    pub dark: Color,
+}
+
+impl WoodTexture {
+    pub fn new(seed: u64, rings: f32, light: Color, dark: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            rings,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for WoodTexture {
+    #[inline(always)]
+    fn value(&self, rec: &HitRecord) -> Color {
+        let p = rec.p;
+        let r = (p.x * p.x + p.z * p.z).sqrt();
+        let grain = r * self.rings + 2. * self.noise.noise(&(4. * p));
+
+        // sharpen the transition from light to dark wood within each ring
+        let t = (grain - grain.floor()).powi(3);
+        (1. - t) * self.light + t * self.dark
+    }
+}
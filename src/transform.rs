@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable, HittableList, collect_lights},
+    light::LightBounds,
+    ray::{FaceNormal, Interval, Ray},
+    vector::{Mat4, Point3, Vec3},
+};
+
+use anyhow::{Result, anyhow};
+
+// instance of an object placed in the world by an affine transform,
+// rays are moved into object space rather than the geometry into world space
+pub struct Transform {
+    pub object: Arc<dyn Hittable + Sync + Send>,
+    pub matrix: Mat4,    // object to world
+    inverse: Mat4,       // world to object
+    normal_matrix: Mat4, // inverse transpose, takes normals to world space
+    det: f32,            // absolute determinant of the linear part, how much volumes scale
+    bbox: Aabb,
+}
+
+#[allow(unused)]
+impl Transform {
+    // fails for a singular matrix, which would flatten the object
+    pub fn new(object: Arc<dyn Hittable + Sync + Send>, matrix: Mat4) -> Result<Self> {
+        let inverse = matrix
+            .inverse()
+            .ok_or_else(|| anyhow!("instance transform is not invertible"))?;
+
+        Ok(Self::with_inverse(object, matrix, inverse))
+    }
+
+    fn with_inverse(object: Arc<dyn Hittable + Sync + Send>, matrix: Mat4, inverse: Mat4) -> Self {
+        // world space box around the eight transformed corners of the object's box
+        let obj_box = object.bounding_box();
+        let mut min = Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut max = Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+
+        for x in [obj_box.x.min, obj_box.x.max] {
+            for y in [obj_box.y.min, obj_box.y.max] {
+                for z in [obj_box.z.min, obj_box.z.max] {
+                    let p = matrix.transform_point(&Point3::new(x, y, z));
+                    min = Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+                    max = Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+                }
+            }
+        }
+
+        let m = &matrix.m;
+        let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+
+        Self {
+            object,
+            matrix,
+            inverse,
+            normal_matrix: inverse.transpose(),
+            det: det.abs(),
+            bbox: Aabb::new(&min, &max),
+        }
+    }
+
+    #[inline(always)]
+    pub fn translate(object: Arc<dyn Hittable + Sync + Send>, offset: &Vec3) -> Self {
+        Self::with_inverse(
+            object,
+            Mat4::translation(offset),
+            Mat4::translation(&-*offset),
+        )
+    }
+
+    #[inline(always)]
+    pub fn rotate_y(object: Arc<dyn Hittable + Sync + Send>, degrees: f32) -> Self {
+        Self::with_inverse(
+            object,
+            Mat4::rotation_y(degrees),
+            Mat4::rotation_y(-degrees),
+        )
+    }
+
+    // fails if any of the factors is zero
+    #[inline(always)]
+    pub fn scale(object: Arc<dyn Hittable + Sync + Send>, factors: &Vec3) -> Result<Self> {
+        Self::new(object, Mat4::scaling(factors))
+    }
+}
+
+impl Hittable for Transform {
+    #[inline(always)]
+    fn hit(&self, r: &Ray, intvl: &Interval) -> Option<HitRecord> {
+        // the direction is not renormalised, so t is the same in both spaces
+        let obj_r = Ray::new(
+            self.inverse.transform_point(&r.origin),
+            self.inverse.transform_vector(&r.dir),
+            r.time,
+        );
+
+        let mut rec = self.object.hit(&obj_r, intvl)?;
+
+        rec.p = self.matrix.transform_point(&rec.p);
+
+        // the side of the surface the ray is on does not change with the transform
+        let normal = self.normal_matrix.transform_vector(rec.normal()).unit_vec();
+        rec.face_normal = match rec.face_normal {
+            FaceNormal::Front(_) => FaceNormal::Front(normal),
+            FaceNormal::Back(_) => FaceNormal::Back(normal),
+        };
+
+        Some(rec)
+    }
+
+    #[inline(always)]
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    #[inline(always)]
+    fn pdf_value(&self, r: &Ray) -> f32 {
+        let obj_dir = self.inverse.transform_vector(&r.dir);
+        let obj_r = Ray::new(self.inverse.transform_point(&r.origin), obj_dir, r.time);
+
+        // a linear map A stretches the solid angle around the unit direction d
+        // by |det A| / |A d|^3
+        let stretch = r.dir.len() / obj_dir.len();
+        self.object.pdf_value(&obj_r) * stretch * stretch * stretch / self.det
+    }
+
+    #[inline(always)]
+    fn random(&self, origin: &Point3, time: f32) -> Vec3 {
+        let obj_dir = self
+            .object
+            .random(&self.inverse.transform_point(origin), time);
+        self.matrix.transform_vector(&obj_dir)
+    }
+
+    // angles only survive rotations and uniform scales, anything else widens the normals to
+    // every direction, while the power grows roughly with the surface area
+    fn light_bounds(&self) -> Option<LightBounds> {
+        let inner = self.object.light_bounds()?;
+
+        let a = self.matrix.transform_vector(&Vec3::new(1., 0., 0.));
+        let b = self.matrix.transform_vector(&Vec3::new(0., 1., 0.));
+        let c = self.matrix.transform_vector(&Vec3::new(0., 0., 1.));
+        let s2 = a.len_squared();
+        let eps = 1e-4 * s2;
+        let conformal = (b.len_squared() - s2).abs() < eps
+            && (c.len_squared() - s2).abs() < eps
+            && a.dot(&b).abs() < eps
+            && a.dot(&c).abs() < eps
+            && b.dot(&c).abs() < eps;
+
+        let w = self.normal_matrix.transform_vector(&inner.w).unit_vec();
+        let cos_theta_o = if conformal { inner.cos_theta_o } else { -1. };
+
+        Some(LightBounds::new(
+            self.bbox.clone(),
+            inner.phi * self.det.powf(2. / 3.),
+            w,
+            cos_theta_o,
+            inner.cos_theta_e,
+            inner.two_sided,
+        ))
+    }
+
+    // each light inside is placed in the world by its own copy of this transform
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut inner = HittableList::new();
+        collect_lights(&self.object, &mut inner);
+
+        for light in inner.objects {
+            lights.add(Arc::new(Transform::with_inverse(
+                light,
+                self.matrix,
+                self.inverse,
+            )));
+        }
+    }
+}
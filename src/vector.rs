@@ -111,6 +111,30 @@ impl Vec3 {
         r_out_perp + r_out_parallel
     }
 
+    // direction over the hemisphere around +z with density cos(theta) / pi
+    #[inline(always)]
+    pub fn random_cosine_direction() -> Vec3 {
+        let r1 = fastrand::f32();
+        let r2 = fastrand::f32();
+
+        let phi = 2. * std::f32::consts::PI * r1;
+        let x = phi.cos() * r2.sqrt();
+        let y = phi.sin() * r2.sqrt();
+        let z = (1. - r2).sqrt();
+
+        Vec3::new(x, y, z)
+    }
+
+    // direction in the cone of half-angle acos(cos_max) around +z, uniform in solid angle
+    #[inline(always)]
+    pub fn random_in_cone(cos_max: f32) -> Vec3 {
+        let cos_theta = 1. - fastrand::f32() * (1. - cos_max);
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let phi = 2. * std::f32::consts::PI * fastrand::f32();
+
+        Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta)
+    }
+
     #[inline(always)]
     pub fn random_in_unit_disk() -> Vec3 {
         loop {
@@ -230,3 +254,227 @@ impl Default for Vec3 {
         Self::new(0., 0., 0.)
     }
 }
+
+// orthonormal basis with `w` along a given direction, for sampling in a local frame
+#[derive(Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+#[allow(unused)]
+impl Onb {
+    #[inline(always)]
+    pub fn new(n: &Vec3) -> Self {
+        let w = n.unit_vec();
+        let a = if w.x.abs() > 0.9 {
+            Vec3::new(0., 1., 0.)
+        } else {
+            Vec3::new(1., 0., 0.)
+        };
+        let v = w.cross(&a).unit_vec();
+        let u = w.cross(&v);
+
+        Self { u, v, w }
+    }
+
+    // local coordinates to world space
+    #[inline(always)]
+    pub fn transform(&self, a: &Vec3) -> Vec3 {
+        a.x * self.u + a.y * self.v + a.z * self.w
+    }
+
+    // world space to local coordinates
+    #[inline(always)]
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(a.dot(&self.u), a.dot(&self.v), a.dot(&self.w))
+    }
+}
+
+// 4x4 matrix for affine transforms of points and vectors, row major
+#[derive(Clone, Copy)]
+pub struct Mat4 {
+    pub m: [[f32; 4]; 4],
+}
+
+#[allow(unused)]
+impl Mat4 {
+    #[inline(always)]
+    pub const fn new(m: [[f32; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    #[inline(always)]
+    pub const fn identity() -> Self {
+        Self::new([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    #[inline(always)]
+    pub const fn translation(offset: &Vec3) -> Self {
+        Self::new([
+            [1., 0., 0., offset.x],
+            [0., 1., 0., offset.y],
+            [0., 0., 1., offset.z],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    #[inline(always)]
+    pub const fn scaling(factors: &Vec3) -> Self {
+        Self::new([
+            [factors.x, 0., 0., 0.],
+            [0., factors.y, 0., 0.],
+            [0., 0., factors.z, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    // counter-clockwise rotation by `degrees` about `axis`, looking down the axis
+    pub fn rotation(axis: &Vec3, degrees: f32) -> Self {
+        let a = axis.unit_vec();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1. - cos;
+
+        Self::new([
+            [
+                t * a.x * a.x + cos,
+                t * a.x * a.y - sin * a.z,
+                t * a.x * a.z + sin * a.y,
+                0.,
+            ],
+            [
+                t * a.x * a.y + sin * a.z,
+                t * a.y * a.y + cos,
+                t * a.y * a.z - sin * a.x,
+                0.,
+            ],
+            [
+                t * a.x * a.z - sin * a.y,
+                t * a.y * a.z + sin * a.x,
+                t * a.z * a.z + cos,
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    #[inline(always)]
+    pub fn rotation_x(degrees: f32) -> Self {
+        Self::rotation(&Vec3::new(1., 0., 0.), degrees)
+    }
+
+    #[inline(always)]
+    pub fn rotation_y(degrees: f32) -> Self {
+        Self::rotation(&Vec3::new(0., 1., 0.), degrees)
+    }
+
+    #[inline(always)]
+    pub fn rotation_z(degrees: f32) -> Self {
+        Self::rotation(&Vec3::new(0., 0., 1.), degrees)
+    }
+
+    #[inline(always)]
+    pub const fn transpose(&self) -> Self {
+        let m = &self.m;
+        Self::new([
+            [m[0][0], m[1][0], m[2][0], m[3][0]],
+            [m[0][1], m[1][1], m[2][1], m[3][1]],
+            [m[0][2], m[1][2], m[2][2], m[3][2]],
+            [m[0][3], m[1][3], m[2][3], m[3][3]],
+        ])
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, None if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap_or(col);
+
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1. / a[col][col];
+            for k in 0..4 {
+                a[col][k] *= scale;
+                inv[col][k] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+
+                let factor = a[row][col];
+                for k in 0..4 {
+                    a[row][k] -= factor * a[col][k];
+                    inv[row][k] -= factor * inv[col][k];
+                }
+            }
+        }
+
+        Some(Self::new(inv))
+    }
+
+    #[inline(always)]
+    pub const fn transform_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+
+        if w == 1. {
+            Point3::new(x, y, z)
+        } else {
+            Point3::new(x / w, y / w, z / w)
+        }
+    }
+
+    // directions ignore the translation part
+    #[inline(always)]
+    pub const fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl ops::Mul for Mat4 {
+    type Output = Mat4;
+
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+
+        Mat4::new(m)
+    }
+}
+
+impl Default for Mat4 {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::identity()
+    }
+}